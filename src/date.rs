@@ -0,0 +1,116 @@
+//! parsing and formatting of twitter dates
+//!
+//! twitter's `created_at` is in the format "Wed Oct 10 20:19:24 +0000 2018".
+//! dates are stored as `SystemTime` so they can be sorted/compared directly
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"]; // 1970-01-01 was a thursday
+
+/// parse a twitter `created_at` date (e.g. "Wed Oct 10 20:19:24 +0000 2018")
+pub fn parse_twitter_date(date: &str) -> Option<SystemTime> {
+  let parts: Vec<&str> = date.split_whitespace().collect();
+  if parts.len() != 6 {
+    return None;
+  }
+  let month = MONTHS.iter().position(|m| *m == parts[1])? as u32 + 1;
+  let day: u32 = parts[2].parse().ok()?;
+  let year: i64 = parts[5].parse().ok()?;
+  let time: Vec<u64> = parts[3].split(':').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+  if time.len() != 3 || time[0] > 23 || time[1] > 59 || time[2] > 60 || !(1..=31).contains(&day) {
+    return None;
+  }
+
+  // "+0000", "-0500", etc.
+  let offset = parts[4];
+  // checked to be ascii so it can be sliced by byte
+  if offset.len() != 5 || !offset.is_ascii() {
+    return None;
+  }
+  let offset_sign = match &offset[..1] { "+" => 1, "-" => -1, _ => return None };
+  let offset_hours: i64 = offset[1..3].parse().ok()?;
+  let offset_mins: i64 = offset[3..].parse().ok()?;
+  let offset_secs = offset_sign * (offset_hours * 3600 + offset_mins * 60);
+
+  let secs = days_from_civil(year, month, day)? * 86400
+    + (time[0] * 3600 + time[1] * 60 + time[2]) as i64
+    - offset_secs;
+  Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+/// parse an ISO 8601 / RFC 3339 date in UTC, as used by cards (e.g. 
+/// "2022-04-14T12:00:00Z"). fractional seconds are ignored
+pub fn parse_iso8601(date: &str) -> Option<SystemTime> {
+  let (date, time) = date.strip_suffix('Z')?.split_once('T')?;
+  let mut date_parts = date.splitn(3, '-');
+  let year: i64 = date_parts.next()?.parse().ok()?;
+  let month: u32 = date_parts.next()?.parse().ok()?;
+  let day: u32 = date_parts.next()?.parse().ok()?;
+  let time = time.split('.').next()?;
+  let time: Vec<u64> = time.split(':').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+  if time.len() != 3 || time[0] > 23 || time[1] > 59 || time[2] > 60
+  || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+    return None;
+  }
+  let secs = days_from_civil(year, month, day)? * 86400 + (time[0] * 3600 + time[1] * 60 + time[2]) as i64;
+  Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+/// format a date as ISO 8601 / RFC 3339 in UTC, e.g. "2018-10-10T20:19:24Z"
+pub fn format_iso8601(date: SystemTime) -> String {
+  let (year, month, day, hours, mins, secs, _) = to_utc(date);
+  format!("{year:04}-{month:02}-{day:02}T{hours:02}:{mins:02}:{secs:02}Z")
+}
+
+/// format a date as RFC 2822 in UTC (as used by RSS and email), e.g.
+/// "Wed, 10 Oct 2018 20:19:24 +0000"
+pub fn format_rfc2822(date: SystemTime) -> String {
+  let (year, month, day, hours, mins, secs, weekday) = to_utc(date);
+  format!("{}, {day:02} {} {year:04} {hours:02}:{mins:02}:{secs:02} +0000",
+    WEEKDAYS[weekday], MONTHS[month as usize - 1])
+}
+
+/// (year, month, day, hours, mins, secs, days since a thursday)
+fn to_utc(date: SystemTime) -> (i64, u32, u32, u64, u64, u64, usize) {
+  // dates before 1970 aren't possible for tweets, so just clamp them
+  let secs = date.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+  let days = (secs / 86400) as i64;
+  let (year, month, day) = civil_from_days(days);
+  let time = secs % 86400;
+  (year, month, day, time / 3600, time % 3600 / 60, time % 60, (days % 7) as usize)
+}
+
+/// days since 1970-01-01 of a date in the proleptic gregorian calendar, or
+/// `None` if the year is outside `0..=9999` (which would overflow when
+/// converted to seconds)
+///
+/// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+  if !(0..=9999).contains(&year) {
+    return None;
+  }
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let year_of_era = year - era * 400;
+  let month = month as i64;
+  let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  Some(era * 146097 + day_of_era - 719468)
+}
+
+/// inverse of `days_from_civil()`
+///
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let days = days + 719468;
+  let era = days.div_euclid(146097);
+  let day_of_era = days - era * 146097;
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let mp = (5 * day_of_year + 2) / 153;
+  let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+  let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month, day)
+}
@@ -0,0 +1,45 @@
+use std::time::{Duration, UNIX_EPOCH};
+use super::date::{parse_twitter_date, parse_iso8601, format_iso8601, format_rfc2822};
+
+#[test]
+fn date_test_parse() {
+  let date = parse_twitter_date("Wed Oct 10 20:19:24 +0000 2018").unwrap();
+  assert_eq!(date, UNIX_EPOCH + Duration::from_secs(1539202764));
+  // offsets are converted to utc
+  assert_eq!(parse_twitter_date("Wed Oct 10 15:19:24 -0500 2018"), Some(date));
+  // leap day
+  assert_eq!(parse_twitter_date("Thu Feb 29 00:00:00 +0000 2024"),
+    Some(UNIX_EPOCH + Duration::from_secs(1709164800)));
+
+  assert_eq!(parse_twitter_date(""), None);
+  assert_eq!(parse_twitter_date("2018-10-10T20:19:24Z"), None);
+  assert_eq!(parse_twitter_date("Wed Foo 10 20:19:24 +0000 2018"), None);
+  assert_eq!(parse_twitter_date("Wed Oct 10 25:19:24 +0000 2018"), None);
+  // a 5 byte offset that isn't 5 chars
+  assert_eq!(parse_twitter_date("Wed Oct 10 20:19:24 €00 2018"), None);
+  // years that would overflow when converted to seconds
+  assert_eq!(parse_twitter_date("Wed Oct 10 20:19:24 +0000 99999999999"), None);
+  assert_eq!(parse_twitter_date("Wed Oct 10 20:19:24 +0000 -99999999999"), None);
+  assert_eq!(parse_twitter_date("Fri Dec 31 23:59:59 +0000 9999"), Some(UNIX_EPOCH + Duration::from_secs(253402300799)));
+}
+
+#[test]
+fn date_test_parse_iso8601() {
+  let date = UNIX_EPOCH + Duration::from_secs(1539202764);
+  assert_eq!(parse_iso8601("2018-10-10T20:19:24Z"), Some(date));
+  assert_eq!(parse_iso8601("2018-10-10T20:19:24.123Z"), Some(date));
+  assert_eq!(parse_iso8601(&format_iso8601(date)), Some(date));
+  assert_eq!(parse_iso8601("2018-10-10T20:19:24"), None);
+  assert_eq!(parse_iso8601("2018-13-10T20:19:24Z"), None);
+  assert_eq!(parse_iso8601("Wed Oct 10 20:19:24 +0000 2018"), None);
+  assert_eq!(parse_iso8601("99999999999-10-10T20:19:24Z"), None);
+}
+
+#[test]
+fn date_test_format() {
+  let date = UNIX_EPOCH + Duration::from_secs(1539202764);
+  assert_eq!(format_iso8601(date), "2018-10-10T20:19:24Z");
+  assert_eq!(format_rfc2822(date), "Wed, 10 Oct 2018 20:19:24 +0000");
+  assert_eq!(format_rfc2822(UNIX_EPOCH + Duration::from_secs(1709164800)), "Thu, 29 Feb 2024 00:00:00 +0000");
+  assert_eq!(format_iso8601(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+}
@@ -0,0 +1,361 @@
+//! downloading the media (images, videos and gifs) of tweets
+//!
+//! files are first downloaded to `<path>.part`, then renamed once complete, so
+//! an interrupted download is resumed (with a `Range` request) the next time
+//! it is run, and files that already exist are skipped
+//!
+//! videos that only have an hls playlist are downloaded by concatenating the
+//! segments (see `hls`). their audio is a separate track, saved next to the
+//! video (see `DownloadedFile::audio`)
+
+use std::{error::Error, fmt, io, path::{Path, PathBuf}};
+use futures_util::{stream, StreamExt};
+use reqwest::{header, StatusCode};
+use tokio::{fs, io::AsyncWriteExt};
+use crate::{types::{Tweet, TweetMedia, MediaKind, VideoVariant}, hls::{self, HlsError}};
+
+/// which variant of a video/gif to download
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum VideoQuality {
+  /// the mp4 with the highest bitrate
+  Best,
+  /// the mp4 with the lowest bitrate
+  Smallest,
+  /// the mp4 with the highest bitrate that is at most this many bits per
+  /// second (or the smallest mp4 if they are all higher)
+  MaxBitrate(u64),
+}
+
+/// options for `download_media()`
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+  /// path of each file relative to the download directory. the placeholders
+  /// are:
+  /// - `{user}`: username of the tweet author
+  /// - `{tweet_id}`: id of the tweet
+  /// - `{media_id}`: id of the media
+  /// - `{index}`: position of the media in the tweet, starting at 1
+  /// - `{kind}`: "photo", "video" or "animated_gif"
+  /// - `{ext}`: file extension, e.g. "jpg", "mp4"
+  pub template: String,
+  /// max number of files downloaded at once
+  pub concurrency: usize,
+  pub video_quality: VideoQuality,
+}
+
+impl Default for DownloadOptions {
+  fn default() -> DownloadOptions {
+    DownloadOptions {
+      template: "{user}/{tweet_id}_{index}.{ext}".to_string(),
+      concurrency: 4,
+      video_quality: VideoQuality::Best,
+    }
+  }
+}
+
+/// a media item to download, along with the tweet it is from
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaDownload {
+  pub user: String,
+  pub tweet_id: String,
+  /// position of the media in the tweet, starting at 1
+  pub index: usize,
+  pub media: TweetMedia,
+}
+
+impl MediaDownload {
+  /// all media of the tweets (including quoted tweets)
+  pub fn from_tweets(tweets: &[Tweet]) -> Vec<MediaDownload> {
+    let mut downloads = Vec::new();
+    for tweet in tweets {
+      let quote = tweet.quote.as_deref();
+      for tweet in [Some(tweet), quote].into_iter().flatten() {
+        for (i, media) in tweet.media.iter().flatten().enumerate() {
+          downloads.push(MediaDownload {
+            user: tweet.user.clone(),
+            tweet_id: tweet.id.clone(),
+            index: i + 1,
+            media: media.clone(),
+          });
+        }
+      }
+    }
+    downloads
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DownloadStatus {
+  Downloaded,
+  /// a partial file from an earlier download was completed
+  Resumed,
+  /// the file already exists
+  Skipped,
+}
+
+/// a successfully downloaded (or skipped) file
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DownloadedFile {
+  pub path: PathBuf,
+  pub url: String,
+  pub status: DownloadStatus,
+  /// size of the file in bytes (`None` if skipped)
+  pub len: Option<u64>,
+  /// the audio of an hls video, which twitter serves as a separate track. it
+  /// is saved next to the video as e.g. "2_1.audio.mp4", and has to be muxed 
+  /// into it to play the video with sound (e.g. `ffmpeg -i 2_1.mp4 -i 
+  /// 2_1.audio.mp4 -c copy out.mp4`)
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub audio: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum DownloadError {
+  /// the video has no mp4 or hls variant
+  NoVariant { media_id: String },
+  Hls(HlsError),
+  Http(reqwest::Error),
+  Status { url: String, status: u16 },
+  Io(io::Error),
+  /// the downloaded file is not the length the server said it was
+  LengthMismatch { url: String, expected: u64, actual: u64 },
+}
+
+impl fmt::Display for DownloadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DownloadError::NoVariant { media_id } => write!(f, "no downloadable variant for media {media_id}"),
+      DownloadError::Hls(error) => write!(f, "hls error: {error}"),
+      DownloadError::Http(error) => write!(f, "http error: {error}"),
+      DownloadError::Status { url, status } => write!(f, "status {status} for {url}"),
+      DownloadError::Io(error) => write!(f, "io error: {error}"),
+      DownloadError::LengthMismatch { url, expected, actual } => {
+        write!(f, "expected {expected} bytes but got {actual} for {url}")
+      },
+    }
+  }
+}
+
+impl Error for DownloadError {}
+
+impl From<reqwest::Error> for DownloadError {
+  fn from(error: reqwest::Error) -> DownloadError {
+    DownloadError::Http(error)
+  }
+}
+
+impl From<HlsError> for DownloadError {
+  fn from(error: HlsError) -> DownloadError {
+    DownloadError::Hls(error)
+  }
+}
+
+impl From<io::Error> for DownloadError {
+  fn from(error: io::Error) -> DownloadError {
+    DownloadError::Io(error)
+  }
+}
+
+/// download all media of the tweets to `dir`, see `download_media()`
+pub async fn download_tweet_media(tweets: &[Tweet], dir: impl AsRef<Path>, options: &DownloadOptions
+) -> Vec<Result<DownloadedFile, DownloadError>> {
+  download_media(MediaDownload::from_tweets(tweets), dir, options).await
+}
+
+/// download the media to `dir`, with at most `options.concurrency` downloads
+/// at once. images are downloaded in their original resolution
+///
+/// returns the result of each download, in the same order as `downloads`
+pub async fn download_media(downloads: Vec<MediaDownload>, dir: impl AsRef<Path>, options: &DownloadOptions
+) -> Vec<Result<DownloadedFile, DownloadError>> {
+  let dir = dir.as_ref();
+  let client = &reqwest::Client::new();
+  stream::iter(downloads)
+    .map(|download| async move {
+      if let Some(playlist_url) = hls_only_url(&download.media, options.video_quality) {
+        return download_hls(client, playlist_url, &download, dir, options).await;
+      }
+      let (url, ext) = media_url(&download.media, options.video_quality)?;
+      let path = dir.join(file_path(&options.template, &download, &ext));
+      if fs::metadata(&path).await.is_ok() {
+        return Ok(DownloadedFile { path, url, status: DownloadStatus::Skipped, len: None, audio: None });
+      }
+      download_file(client, &url, &path).await
+    })
+    .buffered(options.concurrency.max(1))
+    .collect().await
+}
+
+/// the url of the hls playlist of a video without an mp4 variant
+fn hls_only_url(media: &TweetMedia, video_quality: VideoQuality) -> Option<&str> {
+  let info = media.video_info()?;
+  match choose_variant(&info.variants, video_quality) {
+    Some(_) => None,
+    None => info.hls_playlist().map(|variant| variant.url.as_str()),
+  }
+}
+
+/// download a video by concatenating the segments of its hls playlist, and 
+/// its separate audio track (if any) to `DownloadedFile::audio`
+async fn download_hls(client: &reqwest::Client, playlist_url: &str, download: &MediaDownload, dir: &Path,
+  options: &DownloadOptions
+) -> Result<DownloadedFile, DownloadError> {
+  let (video, audio) = hls::resolve_renditions(client, playlist_url, options.video_quality).await?;
+  let path = dir.join(file_path(&options.template, download, &video.extension()));
+  let audio = audio.map(|audio| {
+    let audio_path = path.with_extension(format!("audio.{}", audio.extension()));
+    (audio, audio_path)
+  });
+  let url = playlist_url.to_string();
+
+  let mut status = DownloadStatus::Skipped;
+  let mut len = None;
+  if fs::metadata(&path).await.is_err() {
+    len = Some(hls::download_segments(client, &video, &path).await?);
+    status = DownloadStatus::Downloaded;
+  }
+  if let Some((audio, audio_path)) = &audio {
+    if fs::metadata(audio_path).await.is_err() {
+      hls::download_segments(client, audio, audio_path).await?;
+      status = DownloadStatus::Downloaded;
+    }
+  }
+  Ok(DownloadedFile { path, url, status, len, audio: audio.map(|(_, audio_path)| audio_path) })
+}
+
+/// the url to download the media from, and its file extension
+pub fn media_url(media: &TweetMedia, video_quality: VideoQuality) -> Result<(String, String), DownloadError> {
+  match &media.kind {
+    MediaKind::Photo => {
+      let url = &media.full_img_url;
+      Ok((media.sized_img_url("orig"), url_extension(url).unwrap_or("jpg").to_string()))
+    },
+    MediaKind::Video(info) | MediaKind::AnimatedGif(info) => {
+      let variant = choose_variant(&info.variants, video_quality)
+        .ok_or_else(|| DownloadError::NoVariant { media_id: media.id.clone() })?;
+      Ok((variant.url.clone(), url_extension(&variant.url).unwrap_or("mp4").to_string()))
+    },
+  }
+}
+
+/// choose the mp4 variant of a video for the quality
+pub fn choose_variant(variants: &[VideoVariant], video_quality: VideoQuality) -> Option<&VideoVariant> {
+  let mp4s = || variants.iter().filter(|variant| variant.content_type == "video/mp4");
+  let bitrate = |variant: &&VideoVariant| variant.bitrate.unwrap_or(0);
+  match video_quality {
+    VideoQuality::Best => mp4s().max_by_key(bitrate),
+    VideoQuality::Smallest => mp4s().min_by_key(bitrate),
+    VideoQuality::MaxBitrate(max) => mp4s().filter(|variant| bitrate(variant) <= max).max_by_key(bitrate)
+      .or_else(|| mp4s().min_by_key(bitrate)),
+  }
+}
+
+/// e.g. "https://video.twimg.com/a/1280x720/b.mp4?tag=12" -> "mp4"
+fn url_extension(url: &str) -> Option<&str> {
+  let path = url.split(['?', '#']).next()?;
+  let file_name = path.rsplit('/').next()?;
+  let (_, ext) = file_name.rsplit_once('.')?;
+  match !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()) {
+    true => Some(ext),
+    false => None,
+  }
+}
+
+/// fill in the placeholders of the template
+fn file_path(template: &str, download: &MediaDownload, ext: &str) -> PathBuf {
+  let kind = match download.media.kind {
+    MediaKind::Photo => "photo",
+    MediaKind::Video(_) => "video",
+    MediaKind::AnimatedGif(_) => "animated_gif",
+  };
+  // rm path separators so the values can't escape the download directory
+  let clean = |value: &str| value.replace(['/', '\\'], "_").replace("..", "_");
+  let path = template
+    .replace("{user}", &clean(&download.user))
+    .replace("{tweet_id}", &clean(&download.tweet_id))
+    .replace("{media_id}", &clean(&download.media.id))
+    .replace("{index}", &download.index.to_string())
+    .replace("{kind}", kind)
+    .replace("{ext}", ext);
+  PathBuf::from(path)
+}
+
+/// download `url` to `path`, resuming from `<path>.part` if it exists
+async fn download_file(client: &reqwest::Client, url: &str, path: &Path
+) -> Result<DownloadedFile, DownloadError> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).await?;
+  }
+  let mut part_path = path.as_os_str().to_owned();
+  part_path.push(".part");
+  let part_path = PathBuf::from(part_path);
+
+  let resume_from = fs::metadata(&part_path).await.map(|metadata| metadata.len()).unwrap_or(0);
+  let mut request = client.get(url);
+  if resume_from > 0 {
+    request = request.header(header::RANGE, format!("bytes={resume_from}-"));
+  }
+  let mut response = request.send().await?;
+
+  let status = response.status();
+  let (mut file, start) = match status {
+    StatusCode::PARTIAL_CONTENT if resume_from > 0 => {
+      (fs::OpenOptions::new().append(true).open(&part_path).await?, resume_from)
+    },
+    // the part file is already complete
+    StatusCode::RANGE_NOT_SATISFIABLE if resume_from > 0 => {
+      if let Some(total) = content_range_total(&response).filter(|&total| total != resume_from) {
+        // the part file is corrupt, rm it so the next attempt starts again
+        fs::remove_file(&part_path).await?;
+        return Err(DownloadError::LengthMismatch { url: url.to_string(), expected: total, actual: resume_from });
+      }
+      fs::rename(&part_path, path).await?;
+      return Ok(DownloadedFile { path: path.to_path_buf(), url: url.to_string(), status: DownloadStatus::Resumed, len: Some(resume_from), audio: None });
+    },
+    // the server ignored the range, so start again
+    _ if status.is_success() => (fs::File::create(&part_path).await?, 0),
+    _ => return Err(DownloadError::Status { url: url.to_string(), status: status.as_u16() }),
+  };
+
+  let expected = match status {
+    StatusCode::PARTIAL_CONTENT => content_range_total(&response)
+      .or_else(|| response.content_length().map(|len| start + len)),
+    _ => response.content_length(),
+  };
+  let mut len = start;
+  while let Some(chunk) = response.chunk().await? {
+    file.write_all(&chunk).await?;
+    len += chunk.len() as u64;
+  }
+  file.flush().await?;
+  drop(file);
+
+  if let Some(expected) = expected {
+    if expected != len {
+      // keep the part file so the download can be resumed, unless it's longer 
+      // than it should be (in which case it's corrupt)
+      if len > expected {
+        fs::remove_file(&part_path).await?;
+      }
+      return Err(DownloadError::LengthMismatch { url: url.to_string(), expected, actual: len });
+    }
+  }
+  fs::rename(&part_path, path).await?;
+  let status = match start > 0 {
+    true => DownloadStatus::Resumed,
+    false => DownloadStatus::Downloaded,
+  };
+  Ok(DownloadedFile { path: path.to_path_buf(), url: url.to_string(), status, len: Some(len), audio: None })
+}
+
+/// the total length in a `Content-Range` header, e.g. "bytes 100-199/200" -> 200
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+  let content_range = response.headers().get(header::CONTENT_RANGE)?.to_str().ok()?;
+  content_range.rsplit_once('/')?.1.trim().parse().ok()
+}
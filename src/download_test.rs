@@ -0,0 +1,191 @@
+use std::{collections::{HashMap, VecDeque}, sync::{Arc, Mutex}};
+use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+use crate::{
+  download::{download_media, choose_variant, MediaDownload, DownloadOptions, DownloadStatus, DownloadError, VideoQuality},
+  types::{TweetMedia, MediaKind, VideoInfo, VideoVariant},
+  test_fixtures::{media, temp_dir},
+};
+
+/// a request received by a test server
+pub(crate) struct Request {
+  /// path and query, e.g. "/media/a.jpg?name=orig"
+  pub target: String,
+  /// names are lowercased
+  pub headers: Vec<(String, String)>,
+  pub body: String,
+}
+
+impl Request {
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self.headers.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+  }
+}
+
+/// serve `files` on localhost (supporting `Range: bytes=N-`), returning the
+/// base url and the received requests
+pub(crate) async fn file_server(files: HashMap<&'static str, Vec<u8>>) -> (String, Arc<Mutex<Vec<Request>>>) {
+  scripted_server(files, Vec::new()).await
+}
+
+/// `file_server()`, but responding to the first requests with `statuses` in
+/// order (and an empty body)
+pub(crate) async fn scripted_server(files: HashMap<&'static str, Vec<u8>>, statuses: Vec<u16>
+) -> (String, Arc<Mutex<Vec<Request>>>) {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let base_url = format!("http://{}", listener.local_addr().unwrap());
+  let requests = Arc::new(Mutex::new(Vec::new()));
+  let requests_clone = requests.clone();
+  let mut statuses = VecDeque::from(statuses);
+  tokio::spawn(async move {
+    loop {
+      let (mut socket, _) = listener.accept().await.unwrap();
+      let mut request = Vec::new();
+      let mut buf = [0; 1024];
+      let header_end = loop {
+        if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+          break i + 4;
+        }
+        let n = socket.read(&mut buf).await.unwrap();
+        if n == 0 { break request.len() }
+        request.extend_from_slice(&buf[..n]);
+      };
+      let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+      let target = head.split(' ').nth(1).unwrap_or_default().to_string();
+      let headers: Vec<(String, String)> = head.lines().skip(1)
+        .filter_map(|line| line.split_once(": "))
+        .map(|(name, value)| (name.to_lowercase(), value.to_string()))
+        .collect();
+      let len: usize = headers.iter().find(|(name, _)| name == "content-length")
+        .map_or(0, |(_, value)| value.parse().unwrap());
+      while request.len() < header_end + len {
+        let n = socket.read(&mut buf).await.unwrap();
+        if n == 0 { break }
+        request.extend_from_slice(&buf[..n]);
+      }
+      let body = String::from_utf8_lossy(&request[header_end..]).to_string();
+      let request = Request { target, headers, body };
+
+      let path = request.target.split('?').next().unwrap();
+      let range_start = request.header("range")
+        .and_then(|range| range.strip_prefix("bytes="))
+        .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+      let response = match (statuses.pop_front(), files.get(path), range_start) {
+        (Some(status), _, _) => format!("HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").into_bytes(),
+        (None, None, _) => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+        (None, Some(body), Some(start)) if start >= body.len() => format!(
+          "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+          body.len()).into_bytes(),
+        (None, Some(body), Some(start)) => {
+          let mut response = format!(
+            "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            start, body.len() - 1, body.len(), body.len() - start).into_bytes();
+          response.extend_from_slice(&body[start..]);
+          response
+        },
+        (None, Some(body), None) => {
+          let mut response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len())
+            .into_bytes();
+          response.extend_from_slice(body);
+          response
+        },
+      };
+      requests_clone.lock().unwrap().push(request);
+      socket.write_all(&response).await.unwrap();
+      socket.shutdown().await.ok();
+    }
+  });
+  (base_url, requests)
+}
+
+fn video_info(base_url: &str) -> VideoInfo {
+  let variant = |path: &str, content_type: &str, bitrate: Option<u64>| VideoVariant {
+    url: format!("{base_url}{path}"),
+    content_type: content_type.to_string(),
+    bitrate,
+  };
+  VideoInfo {
+    duration_ms: Some(1000),
+    aspect_ratio: Some((16, 9)),
+    variants: vec![
+      variant("/vid/360.mp4?tag=1", "video/mp4", Some(832000)),
+      variant("/vid/pl.m3u8", "application/x-mpegURL", None),
+      variant("/vid/720.mp4?tag=1", "video/mp4", Some(2176000)),
+    ],
+  }
+}
+
+fn download(user: &str, index: usize, media: TweetMedia) -> MediaDownload {
+  MediaDownload { user: user.to_string(), tweet_id: "100".to_string(), index, media }
+}
+
+#[test]
+fn download_test_choose_variant() {
+  let variants = video_info("").variants;
+  let url = |quality| choose_variant(&variants, quality).map(|variant| variant.url.as_str());
+  assert_eq!(url(VideoQuality::Best), Some("/vid/720.mp4?tag=1"));
+  assert_eq!(url(VideoQuality::Smallest), Some("/vid/360.mp4?tag=1"));
+  assert_eq!(url(VideoQuality::MaxBitrate(1_000_000)), Some("/vid/360.mp4?tag=1"));
+  assert_eq!(url(VideoQuality::MaxBitrate(1)), Some("/vid/360.mp4?tag=1"));
+  assert_eq!(choose_variant(&variants[1..2], VideoQuality::Best), None);
+}
+
+#[tokio::test]
+async fn download_test_download_and_skip() {
+  let (base_url, requests) = file_server(HashMap::from([
+    ("/media/a.jpg", b"photo".to_vec()),
+    ("/vid/720.mp4", b"video 720".to_vec()),
+  ])).await;
+  let dir = temp_dir("download");
+  let downloads = vec![
+    download("epolynya", 1, media("1", &format!("{base_url}/media/a.jpg"), MediaKind::Photo)),
+    download("epolynya", 2, media("2", &format!("{base_url}/thumb.jpg"), MediaKind::Video(video_info(&base_url)))),
+  ];
+
+  let results = download_media(downloads.clone(), &dir, &DownloadOptions::default()).await;
+  let files: Vec<_> = results.into_iter().map(|result| result.unwrap()).collect();
+  assert_eq!(files[0].path, dir.join("epolynya/100_1.jpg"));
+  assert_eq!(files[1].path, dir.join("epolynya/100_2.mp4"));
+  assert!(files.iter().all(|file| file.status == DownloadStatus::Downloaded));
+  assert_eq!(std::fs::read(dir.join("epolynya/100_1.jpg")).unwrap(), b"photo");
+  assert_eq!(std::fs::read(dir.join("epolynya/100_2.mp4")).unwrap(), b"video 720");
+  assert!(requests.lock().unwrap().iter().any(|request| request.target == "/media/a.jpg?name=orig"));
+
+  // already downloaded, so no requests are made
+  let options = DownloadOptions { template: "{user}/{tweet_id}_{index}.{ext}".to_string(), concurrency: 1, ..Default::default() };
+  let results = download_media(downloads, &dir, &options).await;
+  assert!(results.iter().all(|result| result.as_ref().unwrap().status == DownloadStatus::Skipped));
+  assert_eq!(requests.lock().unwrap().len(), 2);
+  std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn download_test_resume() {
+  let (base_url, requests) = file_server(HashMap::from([
+    ("/media/b.png", b"0123456789".to_vec()),
+  ])).await;
+  let dir = temp_dir("resume");
+  let options = DownloadOptions { template: "{media_id}.{ext}".to_string(), ..Default::default() };
+  let downloads = vec![download("a", 1, media("7", &format!("{base_url}/media/b.png"), MediaKind::Photo))];
+
+  std::fs::create_dir_all(&dir).unwrap();
+  std::fs::write(dir.join("7.png.part"), b"01234").unwrap();
+  let file = download_media(downloads.clone(), &dir, &options).await.pop().unwrap().unwrap();
+  assert_eq!(file.status, DownloadStatus::Resumed);
+  assert_eq!(file.len, Some(10));
+  assert_eq!(std::fs::read(dir.join("7.png")).unwrap(), b"0123456789");
+  assert!(!dir.join("7.png.part").exists());
+  assert_eq!(requests.lock().unwrap().len(), 1);
+
+  // a part file longer than the file is an error
+  std::fs::remove_file(dir.join("7.png")).unwrap();
+  std::fs::write(dir.join("7.png.part"), b"0123456789abc").unwrap();
+  let result = download_media(downloads.clone(), &dir, &options).await.pop().unwrap();
+  assert!(matches!(result, Err(DownloadError::LengthMismatch { expected: 10, actual: 13, .. })));
+  assert!(!dir.join("7.png.part").exists());
+
+  // so the next attempt downloads the whole file
+  let file = download_media(downloads, &dir, &options).await.pop().unwrap().unwrap();
+  assert_eq!(file.status, DownloadStatus::Downloaded);
+  assert_eq!(std::fs::read(dir.join("7.png")).unwrap(), b"0123456789");
+  std::fs::remove_dir_all(&dir).ok();
+}
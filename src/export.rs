@@ -0,0 +1,273 @@
+//! writing tweets to CSV and JSON Lines files
+//!
+//! tweets are written one at a time as they arrive, so a search can be
+//! exported page by page without keeping every tweet in memory
+
+use std::io::{self, Write};
+use futures_util::{Stream, StreamExt};
+use crate::{feed::permalink, tweets::text::TextFormat, types::{Tweet, TweetMetrics}};
+
+/// a column of a CSV export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+  Id,
+  /// link to the tweet
+  Url,
+  User,
+  /// `created_at` as ISO 8601, e.g. "2022-04-12T13:16:24Z"
+  Date,
+  /// the display text with links expanded, see `Tweet::render_text()`
+  Text,
+  Lang,
+  Retweets,
+  Replies,
+  Quotes,
+  Faves,
+  Views,
+  /// urls of the images/videos, separated by spaces
+  MediaUrls,
+  QuotedId,
+  /// usernames of the retweeters, separated by spaces
+  RetweetedBy,
+  InReplyToId,
+  InReplyToUser,
+  ConversationId,
+}
+
+impl Column {
+  /// the columns exported by default
+  pub const DEFAULT: [Column; 14] = [
+    Column::Id, Column::User, Column::Date, Column::Text, Column::Retweets, Column::Replies, Column::Quotes,
+    Column::Faves, Column::Views, Column::MediaUrls, Column::QuotedId, Column::RetweetedBy, Column::InReplyToId,
+    Column::InReplyToUser,
+  ];
+
+  /// the header of the column, e.g. "in_reply_to_id"
+  pub fn name(self) -> &'static str {
+    match self {
+      Column::Id => "id",
+      Column::Url => "url",
+      Column::User => "user",
+      Column::Date => "date",
+      Column::Text => "text",
+      Column::Lang => "lang",
+      Column::Retweets => "retweets",
+      Column::Replies => "replies",
+      Column::Quotes => "quotes",
+      Column::Faves => "faves",
+      Column::Views => "views",
+      Column::MediaUrls => "media_urls",
+      Column::QuotedId => "quoted_id",
+      Column::RetweetedBy => "retweeted_by",
+      Column::InReplyToId => "in_reply_to_id",
+      Column::InReplyToUser => "in_reply_to_user",
+      Column::ConversationId => "conversation_id",
+    }
+  }
+
+  /// the value of the column for a tweet (empty if unknown)
+  pub fn value(self, tweet: &Tweet) -> String {
+    let metric = |get: fn(&TweetMetrics) -> Option<u64>| {
+      tweet.metrics.as_ref().and_then(get).map(|n| n.to_string()).unwrap_or_default()
+    };
+    match self {
+      Column::Id => tweet.id.clone(),
+      Column::Url => permalink(tweet),
+      Column::User => tweet.user.clone(),
+      Column::Date => tweet.date_iso8601().unwrap_or_default(),
+      Column::Text => tweet.render_text(TextFormat::Plain),
+      Column::Lang => tweet.lang.clone().unwrap_or_default(),
+      Column::Retweets => metric(|metrics| Some(metrics.retweets)),
+      Column::Replies => metric(|metrics| Some(metrics.replies)),
+      Column::Quotes => metric(|metrics| Some(metrics.quotes)),
+      Column::Faves => metric(|metrics| Some(metrics.faves)),
+      Column::Views => metric(|metrics| metrics.views),
+      Column::MediaUrls => tweet.media.iter().flatten()
+        .map(|media| media.video_url().unwrap_or(&media.full_img_url))
+        .collect::<Vec<&str>>().join(" "),
+      Column::QuotedId => tweet.quoted_id.clone().unwrap_or_default(),
+      Column::RetweetedBy => tweet.retweeted_by.iter().flatten()
+        .map(|retweet| retweet.user.as_str())
+        .collect::<Vec<&str>>().join(" "),
+      Column::InReplyToId => tweet.in_reply_to_status_id.clone().unwrap_or_default(),
+      Column::InReplyToUser => tweet.in_reply_to_screen_name.clone().unwrap_or_default(),
+      Column::ConversationId => tweet.conversation_id.clone().unwrap_or_default(),
+    }
+  }
+}
+
+/// something tweets can be written to one at a time
+pub trait TweetWriter {
+  fn write_tweet(&mut self, tweet: &Tweet) -> io::Result<()>;
+  fn flush(&mut self) -> io::Result<()>;
+
+  /// write every tweet, returning how many were written
+  fn write_all<'a>(&mut self, tweets: impl IntoIterator<Item = &'a Tweet>) -> io::Result<usize> where Self: Sized {
+    let mut count = 0;
+    for tweet in tweets {
+      self.write_tweet(tweet)?;
+      count += 1;
+    }
+    self.flush()?;
+    Ok(count)
+  }
+}
+
+/// write every tweet of the stream as it arrives, returning how many were
+/// written
+pub async fn write_stream<S>(tweets: S, writer: &mut impl TweetWriter) -> io::Result<usize>
+where
+  S: Stream<Item = Tweet>,
+{
+  futures_util::pin_mut!(tweets);
+  let mut count = 0;
+  while let Some(tweet) = tweets.next().await {
+    writer.write_tweet(&tweet)?;
+    count += 1;
+  }
+  writer.flush()?;
+  Ok(count)
+}
+
+/// options for `CsvWriter`
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+  pub columns: Vec<Column>,
+  /// write a header row of the column names
+  pub header: bool,
+  /// start the file with a UTF-8 byte order mark, which Excel needs to read
+  /// non-ascii text (e.g. emoji) correctly
+  pub bom: bool,
+  /// prefix values starting with `=`, `+`, `-`, `@`, a tab or a carriage
+  /// return with `'`, so spreadsheets show them as text instead of running
+  /// them as formulas (which also changes the text of tweets starting with
+  /// one)
+  pub escape_formulas: bool,
+}
+
+impl Default for CsvOptions {
+  fn default() -> CsvOptions {
+    CsvOptions { columns: Column::DEFAULT.to_vec(), header: true, bom: false, escape_formulas: false }
+  }
+}
+
+/// writes tweets as CSV (RFC 4180), one row per tweet
+///
+/// fields containing commas, quotes or line breaks (e.g. multiline tweets) are
+/// quoted. wrap the writer in a `BufWriter` when writing to a file
+pub struct CsvWriter<W: Write> {
+  writer: W,
+  options: CsvOptions,
+  started: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+  pub fn new(writer: W, options: CsvOptions) -> CsvWriter<W> {
+    CsvWriter { writer, options, started: false }
+  }
+
+  pub fn into_inner(self) -> W {
+    self.writer
+  }
+
+  /// write the bom and header if they haven't been written yet
+  fn start(&mut self) -> io::Result<()> {
+    if self.started {
+      return Ok(());
+    }
+    self.started = true;
+    if self.options.bom {
+      self.writer.write_all("\u{feff}".as_bytes())?;
+    }
+    if self.options.header {
+      let names: Vec<&str> = self.options.columns.iter().map(|column| column.name()).collect();
+      self.write_row(&names)?;
+    }
+    Ok(())
+  }
+
+  fn write_row(&mut self, fields: &[impl AsRef<str>]) -> io::Result<()> {
+    let row: Vec<String> = fields.iter().map(|field| escape_csv(field.as_ref())).collect();
+    write!(self.writer, "{}\r\n", row.join(","))
+  }
+}
+
+impl<W: Write> TweetWriter for CsvWriter<W> {
+  fn write_tweet(&mut self, tweet: &Tweet) -> io::Result<()> {
+    self.start()?;
+    let mut values: Vec<String> = self.options.columns.iter().map(|column| column.value(tweet)).collect();
+    if self.options.escape_formulas {
+      for value in &mut values {
+        if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+          value.insert(0, '\'');
+        }
+      }
+    }
+    self.write_row(&values)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    // an export with no tweets still gets a header
+    self.start()?;
+    self.writer.flush()
+  }
+}
+
+/// quote a field if needed, doubling any quotes in it
+fn escape_csv(field: &str) -> String {
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+/// writes tweets as JSON Lines, one object per line
+pub struct JsonLinesWriter<W: Write> {
+  writer: W,
+  #[cfg(feature = "serde")]
+  serde_format: bool,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+  /// write each tweet as `Tweet::to_json()`
+  ///
+  /// note: this is a flattened form for analysis (e.g. with the rendered text
+  /// and url), not the `serde_format` of a tweet, and leaves out cards and
+  /// entity ranges. to be able to deserialize the tweets again, use
+  /// `JsonLinesWriter::serde_format()` (with the `serde` feature)
+  pub fn new(writer: W) -> JsonLinesWriter<W> {
+    JsonLinesWriter {
+      writer,
+      #[cfg(feature = "serde")]
+      serde_format: false,
+    }
+  }
+
+  /// write each tweet in the `serde_format`, wrapped in `Versioned` (e.g.
+  /// `{ "version": 1, "data": { "id": "1513868637307691009", .. } }`)
+  #[cfg(feature = "serde")]
+  pub fn serde_format(writer: W) -> JsonLinesWriter<W> {
+    JsonLinesWriter { writer, serde_format: true }
+  }
+
+  pub fn into_inner(self) -> W {
+    self.writer
+  }
+}
+
+impl<W: Write> TweetWriter for JsonLinesWriter<W> {
+  fn write_tweet(&mut self, tweet: &Tweet) -> io::Result<()> {
+    // serde_json escapes line breaks in strings, so each tweet is one line
+    #[cfg(feature = "serde")]
+    if self.serde_format {
+      serde_json::to_writer(&mut self.writer, &crate::serde_format::Versioned::new(tweet))?;
+      return writeln!(self.writer);
+    }
+    writeln!(self.writer, "{}", tweet.to_json())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.writer.flush()
+  }
+}
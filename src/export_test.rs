@@ -0,0 +1,95 @@
+use futures_util::stream;
+use serde_json::Value;
+use crate::{
+  export::{write_stream, Column, CsvOptions, CsvWriter, JsonLinesWriter, TweetWriter},
+  types::{Tweet, TweetMetrics, Retweet},
+  test_fixtures::tweet,
+};
+
+fn tweets() -> Vec<Tweet> {
+  vec![
+    Tweet {
+      text: "line one, with \"quotes\"\nline two 🎉 &amp; more".to_string(),
+      metrics: Some(TweetMetrics { retweets: 1, replies: 2, quotes: 3, faves: 4, bookmarks: None, views: None }),
+      retweeted_by: Some(vec![
+        Retweet { user: "b".to_string(), id: "10".to_string(), created_at: None },
+        Retweet { user: "c".to_string(), id: "11".to_string(), created_at: None },
+      ]),
+      ..tweet("1", "a")
+    },
+    Tweet {
+      text: "plain".to_string(),
+      quoted_id: Some("1".to_string()),
+      in_reply_to_status_id: Some("1".to_string()),
+      in_reply_to_screen_name: Some("a".to_string()),
+      ..tweet("2", "b")
+    },
+  ]
+}
+
+#[test]
+fn export_test_csv() {
+  println!("CsvWriter  //  quoting of multiline text, quotes and commas");
+  let mut writer = CsvWriter::new(Vec::new(), CsvOptions::default());
+  assert_eq!(writer.write_all(&tweets()).unwrap(), 2);
+  let csv = String::from_utf8(writer.into_inner()).unwrap();
+  assert_eq!(csv, concat!(
+    "id,user,date,text,retweets,replies,quotes,faves,views,media_urls,quoted_id,retweeted_by,in_reply_to_id,in_reply_to_user\r\n",
+    "1,a,,\"line one, with \"\"quotes\"\"\nline two 🎉 & more\",1,2,3,4,,,,b c,,\r\n",
+    "2,b,,plain,,,,,,,1,,1,a\r\n",
+  ));
+
+  // custom columns, no header, bom
+  let options = CsvOptions { columns: vec![Column::Url, Column::RetweetedBy], header: false, bom: true, ..Default::default() };
+  let mut writer = CsvWriter::new(Vec::new(), options);
+  writer.write_all(&tweets()[..1]).unwrap();
+  assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), "\u{feff}https://twitter.com/a/status/1,b c\r\n");
+
+  // no tweets, just the header
+  let mut writer = CsvWriter::new(Vec::new(), CsvOptions { columns: vec![Column::Id, Column::Text], ..Default::default() });
+  writer.write_all(&[]).unwrap();
+  assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), "id,text\r\n");
+}
+
+#[test]
+fn export_test_csv_escape_formulas() {
+  println!("CsvWriter  //  escape_formulas prefixes values spreadsheets would run");
+  let tweets: Vec<Tweet> = ["=1+2", "@a hi", "-5", "+x", "a=b"].iter()
+    .map(|text| Tweet { text: text.to_string(), ..Default::default() })
+    .collect();
+  let options = CsvOptions { columns: vec![Column::Text], header: false, escape_formulas: true, ..Default::default() };
+  let mut writer = CsvWriter::new(Vec::new(), options);
+  writer.write_all(&tweets).unwrap();
+  assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), "'=1+2\r\n'@a hi\r\n'-5\r\n'+x\r\na=b\r\n");
+
+  // off by default
+  let mut writer = CsvWriter::new(Vec::new(), CsvOptions { columns: vec![Column::Text], header: false, ..Default::default() });
+  writer.write_all(&tweets[..1]).unwrap();
+  assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), "=1+2\r\n");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn export_test_json_lines_serde_format() {
+  println!("JsonLinesWriter::serde_format()  //  versioned tweets that deserialize back");
+  use crate::serde_format::Versioned;
+  let mut writer = JsonLinesWriter::serde_format(Vec::new());
+  writer.write_all(&tweets()).unwrap();
+  let jsonl = String::from_utf8(writer.into_inner()).unwrap();
+  let lines: Vec<Versioned<Tweet>> = jsonl.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+  assert_eq!(lines.into_iter().map(|line| line.data).collect::<Vec<Tweet>>(), tweets());
+}
+
+#[tokio::test]
+async fn export_test_json_lines_stream() {
+  println!("write_stream()  //  JSON Lines, one tweet per line");
+  let mut writer = JsonLinesWriter::new(Vec::new());
+  let count = write_stream(stream::iter(tweets()), &mut writer).await.unwrap();
+  assert_eq!(count, 2);
+  let jsonl = String::from_utf8(writer.into_inner()).unwrap();
+  let lines: Vec<Value> = jsonl.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+  assert_eq!(lines.len(), 2);
+  assert_eq!(lines[0]["text"], "line one, with \"quotes\"\nline two 🎉 & more");
+  assert_eq!(lines[0]["retweeted_by"][1]["user"], "c");
+  assert_eq!(lines[1]["quoted_id"], "1");
+}
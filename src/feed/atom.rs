@@ -0,0 +1,43 @@
+//! Atom (RFC 4287)
+
+use crate::{date::format_iso8601, types::Tweet};
+use super::{FeedOptions, escape_xml, item_html, item_title, permalink, updated};
+
+/// render the tweets (in the order given) as an Atom feed
+///
+/// the feed id is `options.self_url` if set, otherwise `options.link`
+pub fn to_atom(tweets: &[Tweet], options: &FeedOptions) -> String {
+  let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+  xml.push_str(&format!("<title>{}</title>\n", escape_xml(&options.title)));
+  if let Some(description) = &options.description {
+    xml.push_str(&format!("<subtitle>{}</subtitle>\n", escape_xml(description)));
+  }
+  let id = options.self_url.as_ref().unwrap_or(&options.link);
+  xml.push_str(&format!("<id>{}</id>\n", escape_xml(id)));
+  xml.push_str(&format!("<link href=\"{}\"/>\n", escape_xml(&options.link)));
+  if let Some(self_url) = &options.self_url {
+    xml.push_str(&format!("<link rel=\"self\" href=\"{}\"/>\n", escape_xml(self_url)));
+  }
+  let feed_updated = format_iso8601(updated(tweets));
+  xml.push_str(&format!("<updated>{feed_updated}</updated>\n"));
+
+  for tweet in tweets {
+    let link = escape_xml(&permalink(tweet));
+    xml.push_str("<entry>\n");
+    xml.push_str(&format!("<title>{}</title>\n", escape_xml(&item_title(tweet))));
+    xml.push_str(&format!("<id>{link}</id>\n"));
+    xml.push_str(&format!("<link href=\"{link}\"/>\n"));
+    xml.push_str(&format!(
+      "<author><name>@{0}</name><uri>https://twitter.com/{0}</uri></author>\n", escape_xml(&tweet.user)));
+    // `updated` is required, tweets can't be edited so it's the same as `published`
+    match tweet.date_iso8601() {
+      Some(date) => xml.push_str(&format!("<published>{date}</published>\n<updated>{date}</updated>\n")),
+      None => xml.push_str(&format!("<updated>{feed_updated}</updated>\n")),
+    }
+    xml.push_str(&format!("<content type=\"html\">{}</content>\n", escape_xml(&item_html(tweet))));
+    xml.push_str("</entry>\n");
+  }
+  xml.push_str("</feed>\n");
+  xml
+}
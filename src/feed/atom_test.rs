@@ -0,0 +1,27 @@
+use super::{FeedOptions, atom::to_atom, rss_test::tweets};
+
+#[test]
+fn atom_test_feed() {
+  println!("to_atom()  //  feed and entry elements");
+  let options = FeedOptions {
+    self_url: Some("https://example.com/feed.atom".to_string()),
+    description: Some("tweets from @a".to_string()),
+    ..FeedOptions::new("@a", "https://twitter.com/a")
+  };
+  let atom = to_atom(&tweets(), &options);
+  assert!(atom.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n"));
+  assert!(atom.contains(concat!(
+    "<title>@a</title>\n<subtitle>tweets from @a</subtitle>\n<id>https://example.com/feed.atom</id>\n",
+    "<link href=\"https://twitter.com/a\"/>\n<link rel=\"self\" href=\"https://example.com/feed.atom\"/>\n",
+    "<updated>2022-04-12T13:16:24Z</updated>\n",
+  )));
+  assert!(atom.contains(concat!(
+    "<entry>\n<title>@b RT @a: cats &amp; dogs https://example.com/?a=1&amp;b=2</title>\n",
+    "<id>https://twitter.com/a/status/2</id>\n<link href=\"https://twitter.com/a/status/2\"/>\n",
+    "<author><name>@a</name><uri>https://twitter.com/a</uri></author>\n",
+    "<published>2022-04-12T13:16:24Z</published>\n<updated>2022-04-12T13:16:24Z</updated>\n",
+    "<content type=\"html\">&lt;p&gt;cats &amp;amp; dogs",
+  )));
+  assert!(atom.ends_with("</entry>\n</feed>\n"));
+}
+
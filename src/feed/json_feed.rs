@@ -0,0 +1,120 @@
+//! JSON Feed 1.1 (https://jsonfeed.org/version/1.1)
+
+use serde_json::{json, Map, Value};
+use crate::{types::{Tweet, TweetMedia, MediaKind}, tweets::text::TextFormat};
+use super::{FeedOptions, item_html, permalink};
+
+/// render the tweets (in the order given) as a JSON Feed
+///
+/// `content_html` is the same html as the RSS/Atom items, and `content_text`
+/// is the plain text with the quoted tweet as a "> " quote. media are
+/// attachments, with their dimensions in a `_twitter` extension
+pub fn to_json_feed(tweets: &[Tweet], options: &FeedOptions) -> Value {
+  let mut feed = Map::new();
+  feed.insert("version".to_string(), json!("https://jsonfeed.org/version/1.1"));
+  feed.insert("title".to_string(), json!(options.title));
+  feed.insert("home_page_url".to_string(), json!(options.link));
+  if let Some(self_url) = &options.self_url {
+    feed.insert("feed_url".to_string(), json!(self_url));
+  }
+  if let Some(description) = &options.description {
+    feed.insert("description".to_string(), json!(description));
+  }
+  feed.insert("items".to_string(), tweets.iter().map(item).collect());
+  Value::Object(feed)
+}
+
+fn item(tweet: &Tweet) -> Value {
+  let mut item = Map::new();
+  item.insert("id".to_string(), json!(tweet.id));
+  item.insert("url".to_string(), json!(permalink(tweet)));
+  item.insert("content_html".to_string(), json!(item_html(tweet)));
+  item.insert("content_text".to_string(), json!(item_text(tweet)));
+  if let Some(date) = tweet.date_iso8601() {
+    item.insert("date_published".to_string(), json!(date));
+  }
+  item.insert("authors".to_string(), json!([author(tweet)]));
+  if !tweet.entities.hashtags.is_empty() {
+    let tags: Vec<&str> = tweet.entities.hashtags.iter().map(|hashtag| hashtag.text.as_str()).collect();
+    item.insert("tags".to_string(), json!(tags));
+  }
+  if let Some(lang) = &tweet.lang {
+    // "und" is twitter's "unknown"
+    if lang != "und" {
+      item.insert("language".to_string(), json!(lang));
+    }
+  }
+  let attachments: Vec<Value> = tweet.media.iter().flatten().map(attachment).collect();
+  if !attachments.is_empty() {
+    item.insert("attachments".to_string(), json!(attachments));
+  }
+  Value::Object(item)
+}
+
+/// the plain text, followed by the quoted tweet's as a "> " quote
+fn item_text(tweet: &Tweet) -> String {
+  let mut text = tweet.render_text(TextFormat::Plain);
+  if let Some(quote) = &tweet.quote {
+    text.push_str("\n\n");
+    let quote_text = format!("@{}: {}", quote.user, item_text(quote));
+    let lines: Vec<String> = quote_text.lines().map(|line| format!("> {line}")).collect();
+    text.push_str(&lines.join("\n"));
+  }
+  text
+}
+
+fn author(tweet: &Tweet) -> Value {
+  let mut author = Map::new();
+  let name = match &tweet.author {
+    Some(tweet_author) => format!("{} (@{})", tweet_author.name, tweet.user),
+    None => format!("@{}", tweet.user),
+  };
+  author.insert("name".to_string(), json!(name));
+  author.insert("url".to_string(), json!(format!("https://twitter.com/{}", tweet.user)));
+  if let Some(avatar_url) = tweet.author.as_ref().and_then(|author| author.avatar_url.as_ref()) {
+    author.insert("avatar".to_string(), json!(avatar_url));
+  }
+  Value::Object(author)
+}
+
+fn attachment(media: &TweetMedia) -> Value {
+  let mut attachment = Map::new();
+  match &media.kind {
+    MediaKind::Photo => {
+      attachment.insert("url".to_string(), json!(media.sized_img_url("orig")));
+      attachment.insert("mime_type".to_string(), json!(image_mime_type(&media.full_img_url)));
+    },
+    MediaKind::Video(info) | MediaKind::AnimatedGif(info) => match info.best_mp4() {
+      Some(variant) => {
+        attachment.insert("url".to_string(), json!(variant.url));
+        attachment.insert("mime_type".to_string(), json!(variant.content_type));
+        if let Some(duration_ms) = info.duration_ms {
+          attachment.insert("duration_in_seconds".to_string(), json!(duration_ms as f64 / 1000.0));
+        }
+      },
+      // no mp4, so attach the poster instead
+      None => {
+        attachment.insert("url".to_string(), json!(media.full_img_url));
+        attachment.insert("mime_type".to_string(), json!(image_mime_type(&media.full_img_url)));
+      },
+    },
+  }
+  if let Some(alt_text) = &media.alt_text {
+    attachment.insert("title".to_string(), json!(alt_text));
+  }
+  // json feed has no field for dimensions, so they're in an extension
+  if let Some((width, height)) = media.original_size {
+    attachment.insert("_twitter".to_string(), json!({ "width": width, "height": height }));
+  }
+  Value::Object(attachment)
+}
+
+/// mime type of a twitter image from its extension (they're almost all jpegs)
+fn image_mime_type(url: &str) -> &'static str {
+  match url.rsplit('.').next().unwrap_or_default() {
+    "png" => "image/png",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    _ => "image/jpeg",
+  }
+}
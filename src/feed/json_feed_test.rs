@@ -0,0 +1,53 @@
+use serde_json::json;
+use crate::types::{TweetAuthor, Hashtag, TextRange};
+use super::{FeedOptions, item_html, json_feed::to_json_feed, rss_test::tweets};
+
+#[test]
+fn json_feed_test_feed() {
+  println!("to_json_feed()  //  items with html, text, authors, tags and attachments");
+  let mut tweets = tweets();
+  let tweet = &mut tweets[0];
+  tweet.author = Some(TweetAuthor {
+    name: "A".to_string(),
+    avatar_url: Some("https://pbs.twimg.com/profile_images/1/a_normal.jpg".to_string()),
+  });
+  tweet.entities.hashtags.push(Hashtag { text: "pets".to_string(), range: TextRange::new(100, 105) });
+  tweet.lang = Some("en".to_string());
+  tweet.media.as_mut().unwrap()[0].original_size = Some((1200, 800));
+  let options = FeedOptions { self_url: Some("https://example.com/feed.json".to_string()), ..FeedOptions::new("@a", "https://twitter.com/a") };
+  let feed = to_json_feed(&tweets, &options);
+
+  assert_eq!(feed["version"], "https://jsonfeed.org/version/1.1");
+  assert_eq!(feed["title"], "@a");
+  assert_eq!(feed["home_page_url"], "https://twitter.com/a");
+  assert_eq!(feed["feed_url"], "https://example.com/feed.json");
+  assert!(feed.get("description").is_none());
+
+  let item = &feed["items"][0];
+  assert_eq!(item["id"], "2");
+  assert_eq!(item["url"], "https://twitter.com/a/status/2");
+  assert_eq!(item["date_published"], "2022-04-12T13:16:24Z");
+  assert_eq!(item["content_html"], item_html(&tweets[0]));
+  assert_eq!(item["content_text"], "cats & dogs https://example.com/?a=1&b=2\n\n> @quoted: the quoted tweet");
+  assert_eq!(item["authors"], json!([{
+    "name": "A (@a)",
+    "url": "https://twitter.com/a",
+    "avatar": "https://pbs.twimg.com/profile_images/1/a_normal.jpg",
+  }]));
+  assert_eq!(item["tags"], json!(["pets"]));
+  assert_eq!(item["language"], "en");
+  assert_eq!(item["attachments"], json!([
+    {
+      "url": "https://pbs.twimg.com/media/3.jpg?name=orig",
+      "mime_type": "image/jpeg",
+      "title": "a \"cat\"",
+      "_twitter": { "width": 1200, "height": 800 },
+    },
+    {
+      "url": "https://video.twimg.com/v.mp4",
+      "mime_type": "video/mp4",
+      "duration_in_seconds": 1.0,
+      "title": "a \"cat\"",
+    },
+  ]));
+}
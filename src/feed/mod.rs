@@ -0,0 +1,133 @@
+//! turning tweets (e.g. the results of a search) into feeds
+//!
+//! each tweet becomes an item whose html body has the expanded text, the
+//! images (and posters of videos, linking to the video), and the quoted tweet
+
+pub mod rss;
+pub mod atom;
+pub mod json_feed;
+
+#[cfg(test)]
+mod rss_test;
+#[cfg(test)]
+mod atom_test;
+#[cfg(test)]
+mod json_feed_test;
+
+use std::time::SystemTime;
+use crate::{
+  types::{Tweet, MediaKind},
+  tweets::text::{render_text, RenderOptions, TextFormat},
+};
+
+/// max number of characters of tweet text in an item title
+const TITLE_LEN: usize = 80;
+
+/// options for the feed as a whole
+#[derive(Debug, Clone)]
+pub struct FeedOptions {
+  pub title: String,
+  /// the page the feed is for, e.g. "https://twitter.com/balajis"
+  pub link: String,
+  /// where the feed itself is served from
+  pub self_url: Option<String>,
+  pub description: Option<String>,
+}
+
+impl Default for FeedOptions {
+  fn default() -> FeedOptions {
+    FeedOptions {
+      title: "Twitter".to_string(),
+      link: "https://twitter.com".to_string(),
+      self_url: None,
+      description: None,
+    }
+  }
+}
+
+impl FeedOptions {
+  pub fn new(title: &str, link: &str) -> FeedOptions {
+    FeedOptions { title: title.to_string(), link: link.to_string(), ..Default::default() }
+  }
+}
+
+/// link to the tweet on twitter
+pub fn permalink(tweet: &Tweet) -> String {
+  format!("https://twitter.com/{}/status/{}", tweet.user, tweet.id)
+}
+
+/// item title, e.g. "@balajis: the first 80 characters of the text…" (or
+/// "@a RT @balajis: .." if it was retweeted)
+pub fn item_title(tweet: &Tweet) -> String {
+  let text = tweet.render_text(TextFormat::Plain).replace('\n', " ");
+  let text = match text.chars().count() > TITLE_LEN {
+    true => format!("{}…", text.chars().take(TITLE_LEN).collect::<String>().trim_end()),
+    false => text,
+  };
+  match tweet.retweeted_by.as_ref().and_then(|retweets| retweets.first()) {
+    Some(retweet) => format!("@{} RT @{}: {text}", retweet.user, tweet.user),
+    None => format!("@{}: {text}", tweet.user),
+  }
+}
+
+/// the html body of an item: the text with expanded links, the media, and the
+/// quoted tweet
+pub fn item_html(tweet: &Tweet) -> String {
+  let mut html = format!("<p>{}</p>", render_text(tweet, &RenderOptions::new(TextFormat::Html)));
+  for media in tweet.media.iter().flatten() {
+    let alt = escape_xml(media.alt_text.as_deref().unwrap_or_default());
+    let img = |src: &str| format!("<img src=\"{}\" alt=\"{alt}\">", escape_xml(src));
+    let media_html = match (&media.kind, media.video_url()) {
+      (MediaKind::Photo, _) | (_, None) => img(&media.sized_img_url("large")),
+      // the image of a video is its poster
+      (_, Some(video_url)) => format!("<a href=\"{}\">{}</a>", escape_xml(video_url), img(&media.full_img_url)),
+    };
+    html.push_str(&format!("<p>{media_html}</p>"));
+  }
+  match (&tweet.quote, &tweet.quoted_id) {
+    (Some(quote), _) => html.push_str(&format!(
+      "<blockquote><p><a href=\"{}\">@{}</a>:</p>{}</blockquote>",
+      escape_xml(&permalink(quote)), escape_xml(&quote.user), item_html(quote),
+    )),
+    (None, Some(quoted_id)) => html.push_str(&format!(
+      "<blockquote><p><a href=\"https://twitter.com/i/status/{}\">quoted tweet</a> unavailable</p></blockquote>",
+      escape_xml(quoted_id),
+    )),
+    (None, None) => {},
+  }
+  if let Some(retweets) = &tweet.retweeted_by {
+    let users: Vec<String> = retweets.iter()
+      .map(|retweet| format!("<a href=\"https://twitter.com/{0}\">@{0}</a>", escape_xml(&retweet.user)))
+      .collect();
+    html.push_str(&format!("<p>Retweeted by {}</p>", users.join(", ")));
+  }
+  html
+}
+
+/// when the feed was last updated: the date of the newest tweet (or now if
+/// there are none)
+fn updated(tweets: &[Tweet]) -> SystemTime {
+  tweets.iter().filter_map(|tweet| tweet.created_at).max().unwrap_or_else(SystemTime::now)
+}
+
+/// escape text for xml (element content or attribute values)
+///
+/// characters xml 1.0 doesn't allow at all (control characters other than
+/// tab and line breaks, U+FFFE and U+FFFF) are removed, as even escaped they
+/// would make the whole document invalid
+pub(crate) fn escape_xml(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      '\t' | '\n' | '\r' => escaped.push(c),
+      '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => {},
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
@@ -0,0 +1,39 @@
+//! RSS 2.0
+
+use crate::{date::format_rfc2822, types::Tweet};
+use super::{FeedOptions, escape_xml, item_html, item_title, permalink, updated};
+
+/// render the tweets (in the order given) as an RSS 2.0 feed
+///
+/// authors are given with `<dc:creator>`, as `<author>` must be an email
+pub fn to_rss(tweets: &[Tweet], options: &FeedOptions) -> String {
+  let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  xml.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+  xml.push_str("<channel>\n");
+  xml.push_str(&format!("<title>{}</title>\n", escape_xml(&options.title)));
+  xml.push_str(&format!("<link>{}</link>\n", escape_xml(&options.link)));
+  // description is required
+  let description = options.description.as_deref().unwrap_or(&options.title);
+  xml.push_str(&format!("<description>{}</description>\n", escape_xml(description)));
+  if let Some(self_url) = &options.self_url {
+    xml.push_str(&format!(
+      "<atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n", escape_xml(self_url)));
+  }
+  xml.push_str(&format!("<lastBuildDate>{}</lastBuildDate>\n", format_rfc2822(updated(tweets))));
+
+  for tweet in tweets {
+    let link = escape_xml(&permalink(tweet));
+    xml.push_str("<item>\n");
+    xml.push_str(&format!("<title>{}</title>\n", escape_xml(&item_title(tweet))));
+    xml.push_str(&format!("<link>{link}</link>\n"));
+    xml.push_str(&format!("<guid isPermaLink=\"true\">{link}</guid>\n"));
+    xml.push_str(&format!("<dc:creator>@{}</dc:creator>\n", escape_xml(&tweet.user)));
+    if let Some(date) = tweet.date_rfc2822() {
+      xml.push_str(&format!("<pubDate>{date}</pubDate>\n"));
+    }
+    xml.push_str(&format!("<description>{}</description>\n", escape_xml(&item_html(tweet))));
+    xml.push_str("</item>\n");
+  }
+  xml.push_str("</channel>\n</rss>\n");
+  xml
+}
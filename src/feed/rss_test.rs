@@ -0,0 +1,103 @@
+use std::time::{Duration, UNIX_EPOCH};
+use crate::{
+  types::{Tweet, TweetURLs, TweetMedia, MediaKind, VideoInfo, VideoVariant, Retweet},
+  test_fixtures::{self, tweet},
+};
+use super::{FeedOptions, rss::to_rss, atom::to_atom, item_title, item_html};
+
+/// media with alt text that needs escaping
+fn media(id: &str, kind: MediaKind) -> TweetMedia {
+  TweetMedia {
+    alt_text: Some("a \"cat\"".to_string()),
+    ..test_fixtures::media(id, &format!("https://pbs.twimg.com/media/{id}.jpg"), kind)
+  }
+}
+
+/// a tweet with a link, a photo, a video and a quote, retweeted by "b"
+pub(crate) fn tweets() -> Vec<Tweet> {
+  let quote = Tweet {
+    text: "the quoted tweet".to_string(),
+    created_at: Some(UNIX_EPOCH + Duration::from_secs(1_649_000_000)),
+    ..tweet("1", "quoted")
+  };
+  let video = VideoInfo {
+    duration_ms: Some(1000),
+    aspect_ratio: None,
+    variants: vec![VideoVariant {
+      url: "https://video.twimg.com/v.mp4".to_string(),
+      content_type: "video/mp4".to_string(),
+      bitrate: Some(832000),
+    }],
+  };
+  let tweet = Tweet {
+    text: "cats &amp; dogs https://t.co/abc https://t.co/m3 https://t.co/q".to_string(),
+    created_at: Some(UNIX_EPOCH + Duration::from_secs(1_649_769_384)),
+    urls: Some(vec![
+      TweetURLs {
+        shortened_url: "https://t.co/abc".to_string(),
+        full_url: "https://example.com/?a=1&b=2".to_string(),
+        display_url: "example.com/?a=1&b=2".to_string(),
+        range: None,
+      },
+      TweetURLs {
+        shortened_url: "https://t.co/q".to_string(),
+        full_url: "https://twitter.com/quoted/status/1".to_string(),
+        display_url: "twitter.com/quoted/status/1".to_string(),
+        range: None,
+      },
+    ]),
+    media: Some(vec![media("3", MediaKind::Photo), media("4", MediaKind::Video(video))]),
+    quoted_id: Some("1".to_string()),
+    quote: Some(Box::new(quote)),
+    retweeted_by: Some(vec![Retweet { user: "b".to_string(), id: "5".to_string(), created_at: None }]),
+    ..tweet("2", "a")
+  };
+  vec![tweet]
+}
+
+#[test]
+fn rss_test_item() {
+  println!("item_title(), item_html()  //  expanded links, images, video poster, quote");
+  let tweet = &tweets()[0];
+  assert_eq!(item_title(tweet), "@b RT @a: cats & dogs https://example.com/?a=1&b=2");
+  let long = Tweet { text: "a".repeat(100), ..Default::default() };
+  assert_eq!(item_title(&long).chars().count(), "@: ".len() + 80 + 1);
+
+  assert_eq!(item_html(tweet), concat!(
+    "<p>cats &amp; dogs <a href=\"https://example.com/?a=1&amp;b=2\">https://example.com/?a=1&amp;b=2</a></p>",
+    "<p><img src=\"https://pbs.twimg.com/media/3.jpg?name=large\" alt=\"a &quot;cat&quot;\"></p>",
+    "<p><a href=\"https://video.twimg.com/v.mp4\"><img src=\"https://pbs.twimg.com/media/4.jpg\" alt=\"a &quot;cat&quot;\"></a></p>",
+    "<blockquote><p><a href=\"https://twitter.com/quoted/status/1\">@quoted</a>:</p><p>the quoted tweet</p></blockquote>",
+    "<p>Retweeted by <a href=\"https://twitter.com/b\">@b</a></p>",
+  ));
+}
+
+#[test]
+fn rss_test_feed() {
+  println!("to_rss()  //  channel and item elements");
+  let options = FeedOptions {
+    self_url: Some("https://example.com/feed.xml".to_string()),
+    ..FeedOptions::new("@a & friends", "https://twitter.com/a")
+  };
+  let rss = to_rss(&tweets(), &options);
+  assert!(rss.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\""));
+  assert!(rss.contains("<title>@a &amp; friends</title>\n<link>https://twitter.com/a</link>\n<description>@a &amp; friends</description>\n"));
+  assert!(rss.contains("<atom:link href=\"https://example.com/feed.xml\" rel=\"self\" type=\"application/rss+xml\"/>"));
+  assert!(rss.contains("<lastBuildDate>Tue, 12 Apr 2022 13:16:24 +0000</lastBuildDate>"));
+  assert!(rss.contains("<guid isPermaLink=\"true\">https://twitter.com/a/status/2</guid>"));
+  assert!(rss.contains("<dc:creator>@a</dc:creator>\n<pubDate>Tue, 12 Apr 2022 13:16:24 +0000</pubDate>"));
+  // the html is escaped
+  assert!(rss.contains("<description>&lt;p&gt;cats &amp;amp; dogs &lt;a href=&quot;"));
+  assert_eq!(rss.matches("<item>").count(), 1);
+  assert!(rss.ends_with("</item>\n</channel>\n</rss>\n"));
+}
+
+#[test]
+fn rss_test_control_chars() {
+  println!("to_rss(), to_atom()  //  characters not allowed in xml are removed");
+  let tweets = vec![Tweet { text: "bell\u{7} null\u{0}\ttab\nline \u{ffff}end".to_string(), ..tweet("1", "a") }];
+  for xml in [to_rss(&tweets, &FeedOptions::default()), to_atom(&tweets, &FeedOptions::default())] {
+    assert!(!xml.contains(['\u{0}', '\u{7}', '\u{ffff}']));
+    assert!(xml.contains("bell null\ttab"));
+  }
+}
@@ -70,8 +70,14 @@ async fn private_api_get(url: Url) -> Result<Value, Box<dyn std::error::Error>>
 
 /// fetch the raw json result of a twitter search query
 pub async fn query_fetch(query: &str) -> Result<Value, Box<dyn std::error::Error>> {
+  query_page_fetch(query, "").await
+}
 
-  let parameters = HashMap::from([
+/// fetch the raw json result of a page of a twitter search query, at a cursor
+/// (or "" for the first page)
+pub async fn query_page_fetch(query: &str, cursor: &str) -> Result<Value, Box<dyn std::error::Error>> {
+
+  let mut parameters = HashMap::from([
     ("include_profile_interstitial_type", "0"), // 1 = include "profile_interstitial_type" attribute in each user object
     ("include_blocking", "0"), // 1 = include "blocking" attribute in each user object
     ("include_blocked_by", "0"), // 1 = include "blocked_by" attribute in each user object
@@ -85,11 +91,11 @@ pub async fn query_fetch(query: &str) -> Result<Value, Box<dyn std::error::Error
     ("include_ext_verified_type", "1"),
     ("skip_status", "0"), // 🚨🚨🚨 effect unclear
     ("cards_platform", "Web-12"), // 🚨🚨🚨 effect unclear (unsure how to edit "Web-12", but commenting out does nothing)
-    ("include_cards", "0"), // 🚨🚨🚨 effect unclear
-    ("include_ext_alt_text", "false"), // true = include "ext_alt_text" in tweet objects, and "profile_image_extensions_alt_text" and "profile_banner_extensions_alt_text" in user objects
+    ("include_cards", "1"), // 1 = include "card" in tweet objects (link previews, polls, etc.)
+    ("include_ext_alt_text", "true"), // true = include "ext_alt_text" in tweet objects, and "profile_image_extensions_alt_text" and "profile_banner_extensions_alt_text" in user objects
     ("include_ext_limited_action_results", "false"),
-    ("include_quote_count", "false"), // true = include "quote_count" in tweet objects (num times the tweet has been quote tweeted)
-    ("include_reply_count", "0"), // 1 = include "reply_count" in tweet objects (num times the tweet has been replied to)
+    ("include_quote_count", "true"), // true = include "quote_count" in tweet objects (num times the tweet has been quote tweeted)
+    ("include_reply_count", "1"), // 1 = include "reply_count" in tweet objects (num times the tweet has been replied to)
     ("tweet_mode", "extended"),
     ("include_ext_collab_control", "true"),
     // need this on bc "entities" stores all url conversions, while "extended_entities" 
@@ -116,9 +122,16 @@ pub async fn query_fetch(query: &str) -> Result<Value, Box<dyn std::error::Error
     // if you instead use the string of a list of names, each name is an 
     // attribute within an "ext" object e.g.
     // `"ext": { "mediaStats": { "r": { "missing": null }, "ttl": -1 }`
-    ("ext", "") //"mediaStats,highlightedLabel,hasNftAvatar,voiceInfo,enrichments,superFollowMetadata,unmentionInfo,editControl,collab_control,vibe"),
+    // "views" = add `"ext": { "views": { "r": { "ok": { "count": "123" } } } }`
+    // to tweet objects
+    ("ext", "views") //"mediaStats,highlightedLabel,hasNftAvatar,voiceInfo,enrichments,superFollowMetadata,unmentionInfo,editControl,collab_control,vibe"),
   ]);
 
+  // add cursor parameter if present
+  if !cursor.is_empty() {
+    parameters.insert("cursor", cursor);
+  }
+
   let url = format!("{}{}", PRIVATE_API_BASE, "2/search/adaptive.json?");
   let url = reqwest::Url::parse_with_params(&url, &parameters)?;
 
@@ -127,6 +140,7 @@ pub async fn query_fetch(query: &str) -> Result<Value, Box<dyn std::error::Error
   Ok(json)
 }
 
+/// fetch the raw timeline instructions of a tweet's `TweetDetail` page
 pub async fn id_fetch(tweet_id: &str, cursor: &str, include_recommended_tweets: bool) -> Result<Value, Box<dyn std::error::Error>> {
   let mut variables: Value = json!({
     "focalTweetId": tweet_id,
@@ -150,11 +164,12 @@ pub async fn id_fetch(tweet_id: &str, cursor: &str, include_recommended_tweets:
   });
   
   // add cursor variable if present
-  if cursor != "" {
+  if !cursor.is_empty() {
     variables["cursor"] = json!(cursor);
   }
   let features = json!({
-    "standardized_nudges_misinfo": false
+    "standardized_nudges_misinfo": false,
+    "view_counts_everywhere_api_enabled": true, // true = add "views" key (val is obj with "count" and "state") to tweet_results.result
   });
   let parameters = HashMap::from([
     ("variables", serde_json::to_string(&variables)?),
@@ -163,22 +178,38 @@ pub async fn id_fetch(tweet_id: &str, cursor: &str, include_recommended_tweets:
   let url = format!("{}{}", PRIVATE_API_BASE, "graphql/L1DeQfPt7n3LtTvrBqkJ2g/TweetDetail?");
   let url = reqwest::Url::parse_with_params(&url, &parameters)?;
 
-  let tweets_json = private_api_get(url).await?
-    .get("data")
-    .and_then(|v| v.get("threaded_conversation_with_injections_v2"))
-    .and_then(|v| v.get("instructions"))
-    .and_then(|v| v.get(0))
-    .and_then(|v| {
-      // no cursor uses "entries"
-      if let Some(e) = v.get("entries") {
-        Some(e)
-      // cursor uses "moduleItems"
-      } else {
-        v.get("moduleItems")
-      }
-  }).unwrap().clone();
+  // the raw `instructions` array, which can be parsed with
+  // `Timeline::from_instructions()`
+  let mut json = private_api_get(url).await?;
+  let instructions = json["data"]["threaded_conversation_with_injections_v2"]
+    ["instructions"].take();
+
+  Ok(instructions)
+}
+
+/// fetch the raw `data` of the `TweetStats` graphql operation for a user
+///
+/// note: these are the profile-level "tweet stats" of a user (e.g. how often 
+/// they tweet), not the counts of a single tweet. parse with 
+/// `tweets::stats::parse_tweet_stats()`
+pub async fn tweet_stats_fetch(user_id: &str) -> Result<Value, Box<dyn std::error::Error>> {
+  let variables = json!({
+    "rest_id": user_id, // the numeric id of the user
+  });
+  // the feature switches listed for the operation in the web client (see below)
+  let features = json!({
+    "profile_foundations_tweet_stats_enabled": true,
+    "profile_foundations_tweet_stats_tweet_frequency": true,
+  });
+  let parameters = HashMap::from([
+    ("variables", serde_json::to_string(&variables)?),
+    ("features", serde_json::to_string(&features)?),
+  ]);
+  let url = format!("{}{}", PRIVATE_API_BASE, "graphql/EvbTkPDT-xQCfupPu0rWMA/TweetStats?");
+  let url = reqwest::Url::parse_with_params(&url, &parameters)?;
 
-  Ok(tweets_json)
+  let mut json = private_api_get(url).await?;
+  Ok(json["data"].take())
 }
 
 // ----------------------------all (?) graphql APIs----------------------------
@@ -0,0 +1,372 @@
+//! HLS (`.m3u8`) playlists of tweet videos and spaces replays
+//!
+//! videos have a master playlist that lists a media playlist for each
+//! resolution (and, for newer videos, separate audio tracks). each media
+//! playlist lists the segments of the video, which are concatenated to get the
+//! full video: `.ts` segments for older videos, or an init segment
+//! (`#EXT-X-MAP`) followed by `.m4s` segments for newer ones
+
+use std::{error::Error, fmt, io, path::{Path, PathBuf}};
+use reqwest::Url;
+use tokio::{fs, io::AsyncWriteExt};
+use crate::download::VideoQuality;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Playlist {
+  Master(MasterPlaylist),
+  Media(MediaPlaylist),
+}
+
+/// a playlist of the available renditions of a video
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MasterPlaylist {
+  /// one per resolution (`#EXT-X-STREAM-INF`)
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub variants: Vec<HlsVariant>,
+  /// `#EXT-X-MEDIA:TYPE=AUDIO`
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub audio_tracks: Vec<AudioTrack>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HlsVariant {
+  /// url of the media playlist
+  pub url: String,
+  /// peak bits per second
+  pub bandwidth: Option<u64>,
+  pub average_bandwidth: Option<u64>,
+  /// width and height
+  pub resolution: Option<(u64, u64)>,
+  /// e.g. "mp4a.40.2,avc1.640020"
+  pub codecs: Option<String>,
+  /// the `group_id` of the audio tracks to play with this variant
+  pub audio_group: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioTrack {
+  /// e.g. "audio-128000"
+  pub group_id: String,
+  pub name: Option<String>,
+  pub language: Option<String>,
+  /// url of the media playlist (`None` if the audio is part of the video
+  /// segments)
+  pub url: Option<String>,
+}
+
+/// a playlist of the segments of a single rendition
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaPlaylist {
+  /// max segment duration in seconds
+  pub target_duration: Option<u64>,
+  /// url of the segment to put before all other segments (`#EXT-X-MAP`)
+  pub init_segment: Option<String>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub segments: Vec<Segment>,
+  /// whether the playlist has `#EXT-X-ENDLIST` (false for live spaces)
+  pub ended: bool,
+}
+
+impl MediaPlaylist {
+  /// total duration of the segments in seconds
+  pub fn duration(&self) -> f64 {
+    self.segments.iter().map(|segment| segment.duration).sum()
+  }
+
+  /// file extension for the concatenated segments: "mp4" for fragmented mp4,
+  /// otherwise the extension of the segments (e.g. "ts", "aac")
+  pub fn extension(&self) -> String {
+    if self.init_segment.is_some() {
+      return "mp4".to_string();
+    }
+    self.segments.first()
+      .and_then(|segment| segment.url.split(['?', '#']).next())
+      .and_then(|path| path.rsplit_once('.'))
+      .map(|(_, ext)| ext.to_string())
+      .filter(|ext| !ext.is_empty() && !ext.contains('/'))
+      .unwrap_or_else(|| "ts".to_string())
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+  pub url: String,
+  /// seconds
+  pub duration: f64,
+}
+
+#[derive(Debug)]
+pub enum HlsError {
+  /// not a valid playlist
+  Parse(String),
+  /// the master playlist has no rendition matching the choice
+  NoRendition,
+  Http(reqwest::Error),
+  Status { url: String, status: u16 },
+  Io(io::Error),
+  /// a segment is not the length the server said it was
+  LengthMismatch { url: String, expected: u64, actual: u64 },
+}
+
+impl fmt::Display for HlsError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      HlsError::Parse(reason) => write!(f, "invalid playlist: {reason}"),
+      HlsError::NoRendition => write!(f, "no matching rendition in playlist"),
+      HlsError::Http(error) => write!(f, "http error: {error}"),
+      HlsError::Status { url, status } => write!(f, "status {status} for {url}"),
+      HlsError::Io(error) => write!(f, "io error: {error}"),
+      HlsError::LengthMismatch { url, expected, actual } => {
+        write!(f, "expected {expected} bytes but got {actual} for {url}")
+      },
+    }
+  }
+}
+
+impl Error for HlsError {}
+
+impl From<reqwest::Error> for HlsError {
+  fn from(error: reqwest::Error) -> HlsError {
+    HlsError::Http(error)
+  }
+}
+
+impl From<io::Error> for HlsError {
+  fn from(error: io::Error) -> HlsError {
+    HlsError::Io(error)
+  }
+}
+
+/// parse a playlist. relative urls are resolved against `base_url` (the url
+/// the playlist was fetched from)
+pub fn parse_playlist(text: &str, base_url: &str) -> Result<Playlist, HlsError> {
+  let base_url = Url::parse(base_url).map_err(|_| HlsError::Parse(format!("invalid url: {base_url}")))?;
+  let mut lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+  if lines.next() != Some("#EXTM3U") {
+    return Err(HlsError::Parse("missing #EXTM3U".to_string()));
+  }
+  let resolve = |uri: &str| base_url.join(uri).map(|url| url.to_string())
+    .map_err(|_| HlsError::Parse(format!("invalid uri: {uri}")));
+
+  let mut master = MasterPlaylist::default();
+  let mut media = MediaPlaylist::default();
+  let mut is_master = false;
+  // attributes of `#EXT-X-STREAM-INF`/`#EXTINF`, which apply to the next uri
+  let mut stream_inf: Option<Vec<(String, String)>> = None;
+  let mut segment_duration: Option<f64> = None;
+
+  for line in lines {
+    if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+      is_master = true;
+      stream_inf = Some(parse_attributes(attributes));
+    } else if let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") {
+      is_master = true;
+      let attributes = parse_attributes(attributes);
+      if attribute(&attributes, "TYPE") == Some("AUDIO") {
+        master.audio_tracks.push(AudioTrack {
+          group_id: attribute(&attributes, "GROUP-ID").unwrap_or_default().to_string(),
+          name: attribute(&attributes, "NAME").map(|s| s.to_string()),
+          language: attribute(&attributes, "LANGUAGE").map(|s| s.to_string()),
+          url: attribute(&attributes, "URI").map(resolve).transpose()?,
+        });
+      }
+    } else if let Some(attributes) = line.strip_prefix("#EXT-X-MAP:") {
+      media.init_segment = attribute(&parse_attributes(attributes), "URI").map(resolve).transpose()?;
+    } else if let Some(duration) = line.strip_prefix("#EXTINF:") {
+      // e.g. "3.000," or "3.000,title"
+      let duration = duration.split(',').next().unwrap_or_default();
+      segment_duration = Some(duration.parse()
+        .map_err(|_| HlsError::Parse(format!("invalid segment duration: {duration}")))?);
+    } else if let Some(target_duration) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+      media.target_duration = target_duration.parse().ok();
+    } else if line == "#EXT-X-ENDLIST" {
+      media.ended = true;
+    } else if line.starts_with('#') {
+      // other tags and comments
+    } else if let Some(attributes) = stream_inf.take() {
+      let resolution = attribute(&attributes, "RESOLUTION")
+        .and_then(|resolution| resolution.split_once('x'))
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+      master.variants.push(HlsVariant {
+        url: resolve(line)?,
+        bandwidth: attribute(&attributes, "BANDWIDTH").and_then(|s| s.parse().ok()),
+        average_bandwidth: attribute(&attributes, "AVERAGE-BANDWIDTH").and_then(|s| s.parse().ok()),
+        resolution,
+        codecs: attribute(&attributes, "CODECS").map(|s| s.to_string()),
+        audio_group: attribute(&attributes, "AUDIO").map(|s| s.to_string()),
+      });
+    } else {
+      media.segments.push(Segment { url: resolve(line)?, duration: segment_duration.take().unwrap_or(0.0) });
+    }
+  }
+
+  match is_master {
+    true => Ok(Playlist::Master(master)),
+    false => Ok(Playlist::Media(media)),
+  }
+}
+
+/// split an attribute list, e.g. `BANDWIDTH=123,CODECS="a,b"` ->
+/// `[("BANDWIDTH", "123"), ("CODECS", "a,b")]`
+fn parse_attributes(attributes: &str) -> Vec<(String, String)> {
+  let mut parsed = Vec::new();
+  let mut rest = attributes.trim();
+  while !rest.is_empty() {
+    let (name, after_name) = match rest.split_once('=') {
+      Some(split) => split,
+      None => break,
+    };
+    let (value, after_value) = match after_name.strip_prefix('"') {
+      Some(quoted) => {
+        let end = quoted.find('"').unwrap_or(quoted.len());
+        (&quoted[..end], quoted.get((end + 1)..).unwrap_or_default())
+      },
+      None => after_name.split_once(',').map_or((after_name, ""), |(value, rest)| (value, rest)),
+    };
+    parsed.push((name.trim().to_string(), value.to_string()));
+    rest = after_value.trim_start_matches(',').trim();
+  }
+  parsed
+}
+
+fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+  attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+}
+
+impl MasterPlaylist {
+  /// choose the variant for the quality, by bandwidth
+  pub fn choose_variant(&self, quality: VideoQuality) -> Option<&HlsVariant> {
+    let bandwidth = |variant: &&HlsVariant| variant.bandwidth.or(variant.average_bandwidth).unwrap_or(0);
+    match quality {
+      VideoQuality::Best => self.variants.iter().max_by_key(bandwidth),
+      VideoQuality::Smallest => self.variants.iter().min_by_key(bandwidth),
+      VideoQuality::MaxBitrate(max) => self.variants.iter()
+        .filter(|variant| bandwidth(variant) <= max).max_by_key(bandwidth)
+        .or_else(|| self.variants.iter().min_by_key(bandwidth)),
+    }
+  }
+
+  /// the audio track to play with a variant (`None` if the variant's audio is
+  /// part of its segments)
+  pub fn audio_track(&self, variant: &HlsVariant) -> Option<&AudioTrack> {
+    let group = variant.audio_group.as_deref()?;
+    self.audio_tracks.iter().find(|track| track.group_id == group && track.url.is_some())
+  }
+}
+
+/// fetch and parse the playlist at `url`
+pub async fn fetch_playlist(client: &reqwest::Client, url: &str) -> Result<Playlist, HlsError> {
+  let response = client.get(url).send().await?;
+  if !response.status().is_success() {
+    return Err(HlsError::Status { url: url.to_string(), status: response.status().as_u16() });
+  }
+  let text = response.text().await?;
+  parse_playlist(&text, url)
+}
+
+/// get the media playlist to download from a master or media playlist url.
+/// for master playlists, the variant is chosen by `quality`
+pub async fn resolve_media_playlist(client: &reqwest::Client, url: &str, quality: VideoQuality
+) -> Result<MediaPlaylist, HlsError> {
+  match fetch_playlist(client, url).await? {
+    Playlist::Media(media) => Ok(media),
+    Playlist::Master(master) => {
+      let variant = master.choose_variant(quality).ok_or(HlsError::NoRendition)?;
+      match fetch_playlist(client, &variant.url).await? {
+        Playlist::Media(media) => Ok(media),
+        Playlist::Master(_) => Err(HlsError::Parse("nested master playlist".to_string())),
+      }
+    },
+  }
+}
+
+/// get the media playlist of the chosen variant, and of its audio track if 
+/// it's served separately (as twitter does, in an `#EXT-X-MEDIA:TYPE=AUDIO` 
+/// group), fetching the master playlist once
+pub async fn resolve_renditions(client: &reqwest::Client, url: &str, quality: VideoQuality
+) -> Result<(MediaPlaylist, Option<MediaPlaylist>), HlsError> {
+  let master = match fetch_playlist(client, url).await? {
+    Playlist::Media(media) => return Ok((media, None)),
+    Playlist::Master(master) => master,
+  };
+  let variant = master.choose_variant(quality).ok_or(HlsError::NoRendition)?;
+  let video = fetch_media_playlist(client, &variant.url).await?;
+  let audio = match master.audio_track(variant).and_then(|track| track.url.as_deref()) {
+    Some(audio_url) => Some(fetch_media_playlist(client, audio_url).await?),
+    None => None,
+  };
+  Ok((video, audio))
+}
+
+async fn fetch_media_playlist(client: &reqwest::Client, url: &str) -> Result<MediaPlaylist, HlsError> {
+  match fetch_playlist(client, url).await? {
+    Playlist::Media(media) => Ok(media),
+    Playlist::Master(_) => Err(HlsError::Parse("nested master playlist".to_string())),
+  }
+}
+
+/// get the media playlist of the audio track of the chosen variant of a
+/// master playlist
+pub async fn resolve_audio_playlist(client: &reqwest::Client, url: &str, quality: VideoQuality
+) -> Result<MediaPlaylist, HlsError> {
+  let master = match fetch_playlist(client, url).await? {
+    // e.g. spaces replays, which only have audio
+    Playlist::Media(media) => return Ok(media),
+    Playlist::Master(master) => master,
+  };
+  let audio_url = master.choose_variant(quality)
+    .and_then(|variant| master.audio_track(variant))
+    .or_else(|| master.audio_tracks.iter().find(|track| track.url.is_some()))
+    .and_then(|track| track.url.clone())
+    .ok_or(HlsError::NoRendition)?;
+  match fetch_playlist(client, &audio_url).await? {
+    Playlist::Media(media) => Ok(media),
+    Playlist::Master(_) => Err(HlsError::Parse("nested master playlist".to_string())),
+  }
+}
+
+/// download the init segment and all segments of the playlist, concatenated
+/// into a single file at `path`
+///
+/// the segments are first written to `<path>.part`, which is renamed once
+/// all segments are downloaded. returns the length of the file
+pub async fn download_segments(client: &reqwest::Client, playlist: &MediaPlaylist, path: &Path
+) -> Result<u64, HlsError> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).await?;
+  }
+  let mut part_path = path.as_os_str().to_owned();
+  part_path.push(".part");
+  let part_path = PathBuf::from(part_path);
+
+  let mut file = fs::File::create(&part_path).await?;
+  let mut len = 0;
+  let urls = playlist.init_segment.iter().chain(playlist.segments.iter().map(|segment| &segment.url));
+  for url in urls {
+    let mut response = client.get(url).send().await?;
+    if !response.status().is_success() {
+      return Err(HlsError::Status { url: url.to_string(), status: response.status().as_u16() });
+    }
+    let expected = response.content_length();
+    let mut segment_len = 0;
+    while let Some(chunk) = response.chunk().await? {
+      file.write_all(&chunk).await?;
+      segment_len += chunk.len() as u64;
+    }
+    if let Some(expected) = expected.filter(|expected| *expected != segment_len) {
+      return Err(HlsError::LengthMismatch { url: url.to_string(), expected, actual: segment_len });
+    }
+    len += segment_len;
+  }
+  file.flush().await?;
+  drop(file);
+  fs::rename(&part_path, path).await?;
+  Ok(len)
+}
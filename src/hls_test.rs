@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use crate::{
+  hls::{parse_playlist, resolve_media_playlist, resolve_audio_playlist, download_segments, Playlist, Segment},
+  download::{download_media, MediaDownload, DownloadOptions, DownloadStatus, VideoQuality},
+  download_test::file_server,
+  types::{MediaKind, VideoInfo, VideoVariant},
+  test_fixtures::{media, temp_dir},
+};
+
+const MASTER: &str = r#"#EXTM3U
+#EXT-X-VERSION:6
+#EXT-X-INDEPENDENT-SEGMENTS
+
+
+#EXT-X-MEDIA:NAME="Audio",TYPE=AUDIO,GROUP-ID="audio-32000",AUTOSELECT=YES,URI="/pl/mp4a/32000/a.m3u8"
+#EXT-X-MEDIA:NAME="Audio",TYPE=AUDIO,GROUP-ID="audio-128000",AUTOSELECT=YES,URI="/pl/mp4a/128000/a.m3u8"
+
+
+#EXT-X-STREAM-INF:AVERAGE-BANDWIDTH=281706,BANDWIDTH=338265,RESOLUTION=480x270,CODECS="mp4a.40.2,avc1.4d001e",AUDIO="audio-32000"
+/pl/avc1/480x270/v.m3u8
+#EXT-X-STREAM-INF:AVERAGE-BANDWIDTH=2289149,BANDWIDTH=2841734,RESOLUTION=1280x720,CODECS="mp4a.40.2,avc1.640020",AUDIO="audio-128000"
+avc1/1280x720/v.m3u8
+"#;
+
+const MEDIA_720: &str = "#EXTM3U
+#EXT-X-VERSION:6
+#EXT-X-MEDIA-SEQUENCE:0
+#EXT-X-TARGETDURATION:3
+#EXT-X-PLAYLIST-TYPE:VOD
+#EXT-X-ALLOW-CACHE:YES
+#EXT-X-INDEPENDENT-SEGMENTS
+#EXT-X-MAP:URI=\"/vid/0/0/init.mp4\"
+#EXTINF:3.000,
+/vid/0/3000/s1.m4s
+#EXTINF:1.500,
+/vid/3000/4500/s2.m4s
+#EXT-X-ENDLIST
+";
+
+const MEDIA_AUDIO: &str = "#EXTM3U
+#EXT-X-TARGETDURATION:3
+#EXTINF:3.000,
+a1.aac
+#EXT-X-ENDLIST
+";
+
+#[test]
+fn hls_test_parse_master() {
+  let playlist = parse_playlist(MASTER, "https://video.twimg.com/ext_tw_video/1/pu/pl/master.m3u8?tag=12").unwrap();
+  let master = match playlist {
+    Playlist::Master(master) => master,
+    Playlist::Media(_) => panic!("not a master playlist"),
+  };
+  assert_eq!(master.variants.len(), 2);
+  let small = &master.variants[0];
+  assert_eq!(small.url, "https://video.twimg.com/pl/avc1/480x270/v.m3u8");
+  assert_eq!((small.bandwidth, small.average_bandwidth), (Some(338265), Some(281706)));
+  assert_eq!(small.resolution, Some((480, 270)));
+  assert_eq!(small.codecs.as_deref(), Some("mp4a.40.2,avc1.4d001e"));
+  // relative to the playlist url
+  assert_eq!(master.variants[1].url, "https://video.twimg.com/ext_tw_video/1/pu/pl/avc1/1280x720/v.m3u8");
+
+  assert_eq!(master.audio_tracks.len(), 2);
+  assert_eq!(master.audio_tracks[0].name.as_deref(), Some("Audio"));
+  assert_eq!(master.choose_variant(VideoQuality::Best), Some(&master.variants[1]));
+  assert_eq!(master.choose_variant(VideoQuality::MaxBitrate(1_000_000)), Some(&master.variants[0]));
+  let audio = master.audio_track(&master.variants[1]).unwrap();
+  assert_eq!(audio.url.as_deref(), Some("https://video.twimg.com/pl/mp4a/128000/a.m3u8"));
+}
+
+#[test]
+fn hls_test_parse_media() {
+  let playlist = parse_playlist(MEDIA_720, "https://video.twimg.com/pl/v.m3u8").unwrap();
+  let media = match playlist {
+    Playlist::Media(media) => media,
+    Playlist::Master(_) => panic!("not a media playlist"),
+  };
+  assert_eq!(media.target_duration, Some(3));
+  assert_eq!(media.init_segment.as_deref(), Some("https://video.twimg.com/vid/0/0/init.mp4"));
+  assert_eq!(media.segments, [
+    Segment { url: "https://video.twimg.com/vid/0/3000/s1.m4s".to_string(), duration: 3.0 },
+    Segment { url: "https://video.twimg.com/vid/3000/4500/s2.m4s".to_string(), duration: 1.5 },
+  ]);
+  assert!(media.ended);
+  assert_eq!(media.duration(), 4.5);
+  assert_eq!(media.extension(), "mp4");
+
+  assert!(parse_playlist("not a playlist", "https://video.twimg.com/").is_err());
+}
+
+#[tokio::test]
+async fn hls_test_download() {
+  let (base_url, _) = file_server(HashMap::from([
+    ("/pl/master.m3u8", MASTER.as_bytes().to_vec()),
+    ("/pl/avc1/1280x720/v.m3u8", MEDIA_720.as_bytes().to_vec()),
+    ("/pl/mp4a/128000/a.m3u8", MEDIA_AUDIO.as_bytes().to_vec()),
+    ("/pl/mp4a/128000/a1.aac", b"aac".to_vec()),
+    ("/vid/0/0/init.mp4", b"init-".to_vec()),
+    ("/vid/0/3000/s1.m4s", b"one-".to_vec()),
+    ("/vid/3000/4500/s2.m4s", b"two".to_vec()),
+  ])).await;
+  let client = reqwest::Client::new();
+  let dir = temp_dir("hls");
+  let master_url = format!("{base_url}/pl/master.m3u8");
+
+  // audio track of the best variant
+  let audio = resolve_audio_playlist(&client, &master_url, VideoQuality::Best).await.unwrap();
+  assert_eq!(audio.extension(), "aac");
+  assert_eq!(download_segments(&client, &audio, &dir.join("a.aac")).await.unwrap(), 3);
+  assert_eq!(std::fs::read(dir.join("a.aac")).unwrap(), b"aac");
+
+  let video = resolve_media_playlist(&client, &master_url, VideoQuality::Best).await.unwrap();
+  assert_eq!(download_segments(&client, &video, &dir.join("v.mp4")).await.unwrap(), 12);
+  assert_eq!(std::fs::read(dir.join("v.mp4")).unwrap(), b"init-one-two");
+
+  // a video that only has the hls variant is downloaded by the downloader,
+  // along with the audio group of the variant
+  let downloads = vec![hls_download(&base_url, &master_url)];
+  let file = download_media(downloads.clone(), &dir, &DownloadOptions::default()).await.pop().unwrap().unwrap();
+  assert_eq!(file.status, DownloadStatus::Downloaded);
+  assert_eq!(file.path, dir.join("a/2_1.mp4"));
+  assert_eq!(std::fs::read(&file.path).unwrap(), b"init-one-two");
+  assert_eq!(file.audio, Some(dir.join("a/2_1.audio.aac")));
+  assert_eq!(std::fs::read(dir.join("a/2_1.audio.aac")).unwrap(), b"aac");
+
+  // the missing audio is downloaded even if the video exists
+  std::fs::remove_file(dir.join("a/2_1.audio.aac")).unwrap();
+  let file = download_media(downloads.clone(), &dir, &DownloadOptions::default()).await.pop().unwrap().unwrap();
+  assert_eq!((file.status, file.len), (DownloadStatus::Downloaded, None));
+  assert_eq!(std::fs::read(dir.join("a/2_1.audio.aac")).unwrap(), b"aac");
+  let file = download_media(downloads, &dir, &DownloadOptions::default()).await.pop().unwrap().unwrap();
+  assert_eq!(file.status, DownloadStatus::Skipped);
+  std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn hls_test_download_muxed() {
+  println!("download_media()  //  hls variant without an audio group has no separate audio");
+  let master = "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=338265,RESOLUTION=480x270\nv.m3u8\n";
+  let (base_url, _) = file_server(HashMap::from([
+    ("/pl/master.m3u8", master.as_bytes().to_vec()),
+    ("/pl/v.m3u8", b"#EXTM3U\n#EXTINF:1.0,\ns1.ts\n#EXT-X-ENDLIST\n".to_vec()),
+    ("/pl/s1.ts", b"ts".to_vec()),
+  ])).await;
+  let dir = temp_dir("hls-muxed");
+  let downloads = vec![hls_download(&base_url, &format!("{base_url}/pl/master.m3u8"))];
+  let file = download_media(downloads, &dir, &DownloadOptions::default()).await.pop().unwrap().unwrap();
+  assert_eq!(file.path, dir.join("a/2_1.ts"));
+  assert_eq!(file.audio, None);
+  std::fs::remove_dir_all(&dir).ok();
+}
+
+/// a video of tweet 2 by "a", whose only variant is the hls playlist
+fn hls_download(base_url: &str, master_url: &str) -> MediaDownload {
+  let kind = MediaKind::Video(VideoInfo {
+    duration_ms: Some(4500),
+    aspect_ratio: Some((16, 9)),
+    variants: vec![VideoVariant { url: master_url.to_string(), content_type: "application/x-mpegURL".to_string(), bitrate: None }],
+  });
+  MediaDownload { user: "a".to_string(), tweet_id: "2".to_string(), index: 1, media: media("1", &format!("{base_url}/thumb.jpg"), kind) }
+}
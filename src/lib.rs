@@ -1,7 +1,42 @@
 pub mod types;
 pub mod tweets;
 pub mod fetch;
+pub mod timeline;
+pub mod snowflake;
+pub mod date;
+pub mod download;
+pub mod hls;
+pub mod polls;
+pub mod watch;
+pub mod sink;
+pub mod feed;
+pub mod export;
+#[cfg(feature = "serde")]
+pub mod serde_format;
 pub mod v1_api;
 
 #[cfg(test)]
-mod fetch_test;
\ No newline at end of file
+pub(crate) mod test_fixtures;
+#[cfg(test)]
+#[allow(unused_variables, clippy::needless_borrow)]
+mod fetch_test;
+#[cfg(test)]
+mod timeline_test;
+#[cfg(test)]
+mod snowflake_test;
+#[cfg(test)]
+mod date_test;
+#[cfg(test)]
+mod download_test;
+#[cfg(test)]
+mod hls_test;
+#[cfg(test)]
+mod polls_test;
+#[cfg(test)]
+mod watch_test;
+#[cfg(test)]
+mod sink_test;
+#[cfg(test)]
+mod export_test;
+#[cfg(all(test, feature = "serde"))]
+mod serde_format_test;
@@ -0,0 +1,224 @@
+//! tracking how the votes of polls change over time
+//!
+//! `watch_polls()` re-fetches the poll cards of tweets on an interval, and
+//! records a snapshot of the vote counts each time, until the polls end (i.e.
+//! `counts_are_final`)
+
+use std::{
+  collections::HashMap, error::Error, fs, future::Future, io::{self, BufRead, BufReader, Write},
+  path::PathBuf, time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use serde_json::{json, Value};
+use tokio::time::sleep;
+use crate::{
+  types::{PollCard, PollChoice, TweetCard},
+  tweets::url::url_to_tweet,
+};
+
+/// the vote counts of a poll at a point in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PollSnapshot {
+  pub tweet_id: String,
+  /// when the snapshot was taken
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_format::time"))]
+  pub time: SystemTime,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub choices: Vec<PollChoice>,
+  pub counts_are_final: bool,
+}
+
+impl PollSnapshot {
+  pub fn new(tweet_id: &str, poll: &PollCard) -> PollSnapshot {
+    PollSnapshot {
+      tweet_id: tweet_id.to_string(),
+      time: SystemTime::now(),
+      choices: poll.choices.clone(),
+      counts_are_final: poll.counts_are_final,
+    }
+  }
+
+  /// as a json object, with `time` in milliseconds since the unix epoch
+  pub fn to_json(&self) -> Value {
+    json!({
+      "tweet_id": self.tweet_id,
+      "time": self.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+      "choices": self.choices.iter()
+        .map(|choice| json!({ "label": choice.label, "count": choice.count }))
+        .collect::<Vec<Value>>(),
+      "counts_are_final": self.counts_are_final,
+    })
+  }
+
+  /// inverse of `to_json()`
+  pub fn from_json(json: &Value) -> Option<PollSnapshot> {
+    Some(PollSnapshot {
+      tweet_id: json["tweet_id"].as_str()?.to_string(),
+      time: UNIX_EPOCH + Duration::from_millis(json["time"].as_u64()?),
+      choices: json["choices"].as_array()?.iter()
+        .map(|choice| Some(PollChoice {
+          label: choice["label"].as_str()?.to_string(),
+          count: choice["count"].as_u64()?,
+        }))
+        .collect::<Option<Vec<PollChoice>>>()?,
+      counts_are_final: json["counts_are_final"].as_bool()?,
+    })
+  }
+}
+
+/// where poll snapshots are recorded
+pub trait PollStore {
+  fn record(&mut self, snapshot: &PollSnapshot) -> io::Result<()>;
+
+  /// all snapshots of a poll, oldest first
+  fn snapshots(&self, tweet_id: &str) -> io::Result<Vec<PollSnapshot>>;
+}
+
+/// keeps snapshots in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPollStore {
+  snapshots: HashMap<String, Vec<PollSnapshot>>,
+}
+
+impl MemoryPollStore {
+  pub fn new() -> MemoryPollStore {
+    MemoryPollStore::default()
+  }
+}
+
+impl PollStore for MemoryPollStore {
+  fn record(&mut self, snapshot: &PollSnapshot) -> io::Result<()> {
+    self.snapshots.entry(snapshot.tweet_id.clone()).or_default().push(snapshot.clone());
+    Ok(())
+  }
+
+  fn snapshots(&self, tweet_id: &str) -> io::Result<Vec<PollSnapshot>> {
+    Ok(self.snapshots.get(tweet_id).cloned().unwrap_or_default())
+  }
+}
+
+/// appends snapshots to a file as json lines (one `PollSnapshot::to_json()`
+/// per line)
+#[derive(Debug, Clone)]
+pub struct JsonLinesPollStore {
+  path: PathBuf,
+}
+
+impl JsonLinesPollStore {
+  /// the file is created when the first snapshot is recorded
+  pub fn new(path: impl Into<PathBuf>) -> JsonLinesPollStore {
+    JsonLinesPollStore { path: path.into() }
+  }
+}
+
+impl PollStore for JsonLinesPollStore {
+  fn record(&mut self, snapshot: &PollSnapshot) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+    writeln!(file, "{}", snapshot.to_json())
+  }
+
+  fn snapshots(&self, tweet_id: &str) -> io::Result<Vec<PollSnapshot>> {
+    let file = match fs::File::open(&self.path) {
+      Ok(file) => file,
+      Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+      Err(error) => return Err(error),
+    };
+    let mut snapshots = Vec::new();
+    for line in BufReader::new(file).lines() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+      let json: Value = serde_json::from_str(&line)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+      let snapshot = PollSnapshot::from_json(&json)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid poll snapshot: {line}")))?;
+      if snapshot.tweet_id == tweet_id {
+        snapshots.push(snapshot);
+      }
+    }
+    Ok(snapshots)
+  }
+}
+
+/// options for `watch_polls()`
+#[derive(Debug, Clone)]
+pub struct PollWatchOptions {
+  /// time between fetches of each poll
+  pub interval: Duration,
+  /// stop after this many rounds of fetches, even if some polls haven't ended
+  pub max_rounds: Option<usize>,
+  /// stop fetching a poll after this many failed fetches in a row (e.g. the
+  /// tweet was deleted, or the account made protected)
+  pub max_failures: u32,
+}
+
+impl Default for PollWatchOptions {
+  fn default() -> PollWatchOptions {
+    PollWatchOptions {
+      interval: Duration::from_secs(15 * 60),
+      max_rounds: None,
+      max_failures: 5,
+    }
+  }
+}
+
+/// fetch the poll of a tweet (`None` if the tweet has no poll)
+pub async fn fetch_poll(tweet_id: &str) -> Result<Option<PollCard>, Box<dyn Error>> {
+  match url_to_tweet(tweet_id).await?.card {
+    Some(TweetCard::Poll(poll)) => Ok(Some(poll)),
+    _ => Ok(None),
+  }
+}
+
+/// snapshot the polls of the tweets every `options.interval` until they have
+/// all ended, see `watch_polls_with()`
+pub async fn watch_polls(tweet_ids: &[&str], store: &mut impl PollStore, options: &PollWatchOptions
+) -> io::Result<()> {
+  watch_polls_with(tweet_ids, store, options, |tweet_id| async move {
+    fetch_poll(&tweet_id).await.map_err(|error| error.to_string())
+  }).await
+}
+
+/// snapshot the polls of the tweets every `options.interval` until they have
+/// all ended, using `fetch` to get each poll
+///
+/// tweets without a poll are dropped. if fetching a poll fails, it is retried
+/// next round, until it has failed `options.max_failures` times in a row. only
+/// errors from the store are returned
+pub async fn watch_polls_with<F, Fut, E>(tweet_ids: &[&str], store: &mut impl PollStore,
+  options: &PollWatchOptions, mut fetch: F
+) -> io::Result<()>
+where
+  F: FnMut(String) -> Fut,
+  Fut: Future<Output = Result<Option<PollCard>, E>>,
+{
+  // the tweet ids, and their failed fetches in a row
+  let mut pending: Vec<(String, u32)> = tweet_ids.iter().map(|id| (id.to_string(), 0)).collect();
+  let mut round = 0;
+  while !pending.is_empty() {
+    if round > 0 {
+      sleep(options.interval).await;
+    }
+    let mut still_pending = Vec::new();
+    for (tweet_id, failures) in pending {
+      match fetch(tweet_id.clone()).await {
+        Ok(Some(poll)) => {
+          store.record(&PollSnapshot::new(&tweet_id, &poll))?;
+          if !poll.counts_are_final {
+            still_pending.push((tweet_id, 0));
+          }
+        },
+        Ok(None) => {},
+        Err(_) if failures + 1 < options.max_failures => still_pending.push((tweet_id, failures + 1)),
+        Err(_) => {},
+      }
+    }
+    pending = still_pending;
+    round += 1;
+    if options.max_rounds.map_or(false, |max_rounds| round >= max_rounds) {
+      break;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,101 @@
+use std::{cell::RefCell, collections::HashMap, time::{Duration, UNIX_EPOCH}};
+use crate::{
+  polls::{watch_polls_with, PollSnapshot, PollStore, MemoryPollStore, JsonLinesPollStore, PollWatchOptions},
+  types::{PollCard, PollChoice},
+  test_fixtures::temp_dir,
+};
+
+fn poll(counts: &[u64], counts_are_final: bool) -> PollCard {
+  PollCard {
+    choices: counts.iter().enumerate()
+      .map(|(i, count)| PollChoice { label: format!("choice {}", i + 1), count: *count })
+      .collect(),
+    counts_are_final,
+    ..Default::default()
+  }
+}
+
+fn counts(snapshots: &[PollSnapshot]) -> Vec<Vec<u64>> {
+  snapshots.iter().map(|snapshot| snapshot.choices.iter().map(|choice| choice.count).collect()).collect()
+}
+
+#[tokio::test]
+async fn polls_test_watch_polls() {
+  println!("watch_polls_with()  //  snapshots each poll until its counts are final");
+  // what each fetch returns, in order, per tweet
+  let responses = RefCell::new(HashMap::from([
+    ("1", vec![Ok(Some(poll(&[1, 0], false))), Err("rate limited"), Ok(Some(poll(&[3, 2], true)))]),
+    ("2", vec![Ok(Some(poll(&[5, 5], false))), Ok(Some(poll(&[6, 9], false))), Ok(Some(poll(&[7, 9], true)))]),
+    ("3", vec![Ok(None)]),
+  ]));
+  let fetch = |tweet_id: String| {
+    let response = responses.borrow_mut().get_mut(tweet_id.as_str()).unwrap().remove(0);
+    async move { response }
+  };
+  let mut store = MemoryPollStore::new();
+  let options = PollWatchOptions { interval: Duration::ZERO, ..Default::default() };
+  watch_polls_with(&["1", "2", "3"], &mut store, &options, fetch).await.unwrap();
+
+  let snapshots = store.snapshots("1").unwrap();
+  assert_eq!(counts(&snapshots), vec![vec![1, 0], vec![3, 2]]);
+  assert_eq!(snapshots.iter().map(|s| s.counts_are_final).collect::<Vec<_>>(), vec![false, true]);
+  assert_eq!(counts(&store.snapshots("2").unwrap()), vec![vec![5, 5], vec![6, 9], vec![7, 9]]);
+  assert!(store.snapshots("3").unwrap().is_empty());
+  assert!(responses.borrow().values().all(|responses| responses.is_empty()));
+}
+
+#[tokio::test]
+async fn polls_test_max_rounds() {
+  println!("watch_polls_with()  //  stops after max_rounds even if polls haven't ended");
+  let mut store = MemoryPollStore::new();
+  let options = PollWatchOptions { interval: Duration::ZERO, max_rounds: Some(2), ..Default::default() };
+  watch_polls_with(&["1"], &mut store, &options, |_| async { Ok::<_, ()>(Some(poll(&[1], false))) })
+    .await.unwrap();
+  assert_eq!(store.snapshots("1").unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn polls_test_max_failures() {
+  println!("watch_polls_with()  //  stops fetching a poll that fails max_failures times in a row");
+  let fetches = RefCell::new(0);
+  let mut store = MemoryPollStore::new();
+  let options = PollWatchOptions { interval: Duration::ZERO, max_failures: 3, ..Default::default() };
+  watch_polls_with(&["1"], &mut store, &options, |_| {
+    *fetches.borrow_mut() += 1;
+    async { Err::<Option<PollCard>, _>("tweet can't be viewed") }
+  }).await.unwrap();
+  assert_eq!(*fetches.borrow(), 3);
+  assert!(store.snapshots("1").unwrap().is_empty());
+}
+
+#[test]
+fn polls_test_json_lines_store() {
+  println!("JsonLinesPollStore  //  snapshots round-trip through the file");
+  let dir = temp_dir("polls");
+  std::fs::create_dir_all(&dir).unwrap();
+  let mut store = JsonLinesPollStore::new(dir.join("polls.jsonl"));
+  assert!(store.snapshots("1").unwrap().is_empty());
+
+  let snapshot = |tweet_id: &str, ms: u64, counts: &[u64], counts_are_final: bool| PollSnapshot {
+    time: UNIX_EPOCH + Duration::from_millis(ms),
+    ..PollSnapshot::new(tweet_id, &poll(counts, counts_are_final))
+  };
+  let snapshots = vec![
+    snapshot("1", 1_660_000_000_000, &[1, 2], false),
+    snapshot("2", 1_660_000_000_500, &[0, 0, 0], false),
+    snapshot("1", 1_660_000_900_000, &[4, 2], true),
+  ];
+  for snapshot in &snapshots {
+    store.record(snapshot).unwrap();
+  }
+  assert_eq!(store.snapshots("1").unwrap(), vec![snapshots[0].clone(), snapshots[2].clone()]);
+  assert_eq!(store.snapshots("2").unwrap(), vec![snapshots[1].clone()]);
+
+  let contents = std::fs::read_to_string(dir.join("polls.jsonl")).unwrap();
+  assert_eq!(contents.lines().count(), 3);
+  assert_eq!(
+    contents.lines().next().unwrap(),
+    r#"{"choices":[{"count":1,"label":"choice 1"},{"count":2,"label":"choice 2"}],"counts_are_final":false,"time":1660000000000,"tweet_id":"1"}"#,
+  );
+  std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,108 @@
+//! the serialized form of the public types (with the `serde` feature)
+//!
+//! the format is the same for every serde data format, and is stable within a
+//! `FORMAT_VERSION`:
+//!
+//! - fields have the same names as in rust (snake_case)
+//! - enums with data are objects with a `"type"` field naming the variant in
+//!   snake_case, e.g. `{ "type": "photo" }` or `{ "type": "video",
+//!   "duration_ms": 1000, "variants": [..] }`
+//! - enums with variants holding a single value that isn't a struct
+//!   (`WatchSource`, `TimelineInstruction`, `EntryContent`, `TimelineItem`,
+//!   `VideoQuality`) have the value in a `"value"` field, e.g.
+//!   `{ "type": "query", "value": ".." }`
+//! - enums without data (`DownloadStatus`) are strings, e.g. `"resumed"`.
+//!   `CursorType` is a string (e.g. `"show_more"`), or `{ "other": ".." }`
+//! - dates are ISO 8601 strings in UTC, e.g. `"2022-04-12T13:16:24Z"`, with
+//!   fractional seconds only if they have any
+//! - `TweetId`s are strings, as they don't fit in a javascript number
+//! - tuples (e.g. `original_size`) are arrays
+//! - raw twitter json (e.g. `TimelineItem::Tweet`) is kept as is
+//!
+//! `Option` fields and lists (including `entities`) can be missing, and are
+//! deserialized as `None` or empty. so fields added within a version are
+//! always optional, and data serialized by an older release of the same
+//! version can still be deserialized. renaming or removing fields, or changing
+//! their type, increments `FORMAT_VERSION`. to store the version with the
+//! data, wrap it in `Versioned`
+
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use crate::date::{format_iso8601, parse_iso8601};
+
+/// the version of the format described in the module docs
+pub const FORMAT_VERSION: u32 = 1;
+
+/// data tagged with the version of the format it was serialized with, e.g.
+/// `{ "version": 1, "data": { "id": "1513868637307691009", .. } }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+  pub version: u32,
+  pub data: T,
+}
+
+impl<T> Versioned<T> {
+  /// wrap `data` with the current `FORMAT_VERSION`
+  pub fn new(data: T) -> Versioned<T> {
+    Versioned { version: FORMAT_VERSION, data }
+  }
+}
+
+/// format a date with its fractional seconds (if any), e.g.
+/// "2022-04-12T13:16:24.5Z"
+fn format_time(time: SystemTime) -> String {
+  let nanos = time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+  let formatted = format_iso8601(time);
+  if nanos == 0 {
+    return formatted;
+  }
+  let fraction = format!("{nanos:09}");
+  format!("{}.{}Z", formatted.trim_end_matches('Z'), fraction.trim_end_matches('0'))
+}
+
+/// inverse of `format_time()`
+fn parse_time(time: &str) -> Option<SystemTime> {
+  // `parse_iso8601()` ignores fractional seconds, so add them back
+  let secs = parse_iso8601(time)?;
+  let nanos = match time.trim_end_matches('Z').split_once('.') {
+    Some((_, fraction)) if (1..=9).contains(&fraction.len()) => format!("{fraction:0<9}").parse().ok()?,
+    Some(_) => return None,
+    None => 0,
+  };
+  Some(secs + Duration::from_nanos(nanos))
+}
+
+/// `#[serde(with = "..")]` for `SystemTime` fields
+pub(crate) mod time {
+  use std::time::SystemTime;
+  use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&super::format_time(*time))
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+    let time = String::deserialize(deserializer)?;
+    super::parse_time(&time).ok_or_else(|| D::Error::custom(format!("invalid ISO 8601 date: {time}")))
+  }
+}
+
+/// `#[serde(with = "..")]` for `Option<SystemTime>` fields (which also need
+/// `#[serde(default)]`, as `with` stops missing fields being `None`)
+pub(crate) mod option_time {
+  use std::time::SystemTime;
+  use serde::{Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error> {
+    match time {
+      Some(time) => super::time::serialize(time, serializer),
+      None => serializer.serialize_none(),
+    }
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SystemTime>, D::Error> {
+    #[derive(Deserialize)]
+    struct Time(#[serde(with = "super::time")] SystemTime);
+    Ok(Option::<Time>::deserialize(deserializer)?.map(|Time(time)| time))
+  }
+}
@@ -0,0 +1,165 @@
+use std::{path::PathBuf, time::{Duration, UNIX_EPOCH}};
+use serde_json::json;
+use crate::{
+  serde_format::{Versioned, FORMAT_VERSION},
+  snowflake::TweetId,
+  timeline::{CursorType, Timeline},
+  hls::{Playlist, MediaPlaylist, Segment},
+  download::{VideoQuality, DownloadedFile, DownloadStatus},
+  types::{
+    Tweet, TweetAuthor, TweetItem, TweetURLs, TweetEntities, Hashtag, Mention, TextRange, TweetMedia, MediaKind,
+    VideoInfo, VideoVariant, MediaSize, MediaCrop, TweetCard, PollCard, PollChoice, TweetMetrics, Retweet,
+  },
+  watch::WatchSource,
+  test_fixtures::{self, media},
+};
+
+/// a tweet with (nearly) every field set
+fn full_tweet() -> Tweet {
+  let video = VideoInfo {
+    duration_ms: Some(1500),
+    aspect_ratio: Some((16, 9)),
+    variants: vec![VideoVariant {
+      url: "https://video.twimg.com/v.mp4".to_string(),
+      content_type: "video/mp4".to_string(),
+      bitrate: Some(832000),
+    }],
+  };
+  Tweet {
+    author: Some(TweetAuthor { name: "Epolynya".to_string(), avatar_url: None }),
+    text: "@a #rust &amp; https://t.co/abc https://t.co/m7".to_string(),
+    display_text_range: Some(TextRange::new(3, 30)),
+    entities: TweetEntities {
+      hashtags: vec![Hashtag { text: "rust".to_string(), range: TextRange::new(3, 8) }],
+      mentions: vec![Mention { screen_name: "a".to_string(), name: Some("A".to_string()), id: None, range: TextRange::new(0, 2) }],
+      cashtags: Vec::new(),
+    },
+    // fractional seconds survive the round trip
+    created_at: Some(UNIX_EPOCH + Duration::new(1_649_769_384, 250_000_000)),
+    media: Some(vec![TweetMedia {
+      range: Some(TextRange::new(31, 46)),
+      alt_text: Some("alt".to_string()),
+      original_size: Some((1920, 1080)),
+      crops: vec![MediaCrop { x: 0, y: 0, width: 1920, height: 1080 }],
+      sizes: vec![MediaSize { name: "small".to_string(), width: 680, height: 383, resize: "fit".to_string() }],
+      ..media("7", "https://pbs.twimg.com/media/7.jpg", MediaKind::Video(video))
+    }]),
+    urls: Some(vec![TweetURLs {
+      shortened_url: "https://t.co/abc".to_string(),
+      full_url: "https://example.com".to_string(),
+      display_url: "example.com".to_string(),
+      range: Some(TextRange::new(15, 30)),
+    }]),
+    quote: Some(Box::new(test_fixtures::tweet("1", "q"))),
+    quoted_id: Some("1".to_string()),
+    in_reply_to_status_id: Some("2".to_string()),
+    reply_mentions: Some(vec!["a".to_string()]),
+    conversation_id: Some("2".to_string()),
+    lang: Some("en".to_string()),
+    metrics: Some(TweetMetrics { retweets: 1, replies: 2, quotes: 3, faves: 4, bookmarks: None, views: Some(5) }),
+    card: Some(TweetCard::Poll(PollCard {
+      choices: vec![PollChoice { label: "yes".to_string(), count: 3 }],
+      end_time: Some(UNIX_EPOCH + Duration::from_secs(1_650_000_000)),
+      counts_are_final: true,
+      ..Default::default()
+    })),
+    retweeted_by: Some(vec![Retweet { user: "b".to_string(), id: "3".to_string(), created_at: None }]),
+    ..test_fixtures::tweet("1513868637307691009", "epolynya")
+  }
+}
+
+#[test]
+fn serde_format_test_round_trip() {
+  println!("Tweet  //  serializes and deserializes back to an equal value");
+  let tweet = full_tweet();
+  let json = serde_json::to_string(&tweet).unwrap();
+  assert_eq!(serde_json::from_str::<Tweet>(&json).unwrap(), tweet);
+
+  let items = vec![
+    TweetItem::Tweet(tweet),
+    TweetItem::Tombstone { text: "This Tweet was deleted".to_string(), id: Some("4".to_string()) },
+    TweetItem::Cursor { kind: CursorType::Other("Gap".to_string()), value: "c".to_string() },
+    TweetItem::Unsupported { kind: "TimelineUser".to_string() },
+  ];
+  let json = serde_json::to_string(&Versioned::new(items.clone())).unwrap();
+  let versioned: Versioned<Vec<TweetItem>> = serde_json::from_str(&json).unwrap();
+  assert_eq!(versioned.version, FORMAT_VERSION);
+  assert_eq!(versioned.data, items);
+}
+
+#[test]
+fn serde_format_test_format() {
+  println!("serde_format  //  field names, enum tags, dates and ids");
+  let json = serde_json::to_value(full_tweet()).unwrap();
+  assert_eq!(json["id"], "1513868637307691009");
+  assert_eq!(json["created_at"], "2022-04-12T13:16:24.25Z");
+  assert_eq!(json["display_text_range"], json!({ "start": 3, "end": 30 }));
+  assert_eq!(json["media"][0]["kind"]["type"], "video");
+  assert_eq!(json["media"][0]["kind"]["aspect_ratio"], json!([16, 9]));
+  assert_eq!(json["card"]["type"], "poll");
+  assert_eq!(json["card"]["end_time"], "2022-04-15T05:20:00Z");
+  assert_eq!(json["quote"]["created_at"], json!(null));
+
+  let cursor = TweetItem::Cursor { kind: CursorType::ShowMore, value: "c".to_string() };
+  assert_eq!(serde_json::to_value(cursor).unwrap(), json!({ "type": "cursor", "kind": "show_more", "value": "c" }));
+  assert_eq!(serde_json::to_value(TweetId(1513868637307691009)).unwrap(), json!("1513868637307691009"));
+  assert_eq!(serde_json::from_value::<TweetId>(json!("42")).unwrap(), TweetId(42));
+  assert_eq!(
+    serde_json::to_value(WatchSource::Query("from:a".to_string())).unwrap(),
+    json!({ "type": "query", "value": "from:a" }),
+  );
+  assert!(serde_json::from_value::<Tweet>(json!({ "created_at": "yesterday" })).is_err());
+}
+
+#[test]
+fn serde_format_test_missing_fields() {
+  println!("Tweet  //  optional fields can be missing");
+  let tweet: Tweet = serde_json::from_value(json!({ "id": "1", "user": "a", "text": "hi" })).unwrap();
+  assert_eq!(tweet, Tweet { text: "hi".to_string(), ..test_fixtures::tweet("1", "a") });
+
+  // lists inside other types can be missing too
+  let media: TweetMedia = serde_json::from_value(json!({
+    "id": "7", "shortened_img_url": "", "full_img_url": "", "kind": { "type": "photo" },
+  })).unwrap();
+  assert!(media.crops.is_empty() && media.sizes.is_empty());
+}
+
+#[test]
+fn serde_format_test_other_types() {
+  println!("serde_format  //  timeline, hls and download types");
+  let timeline = Timeline::from_instructions(&json!([{ "type": "TimelineAddEntries", "entries": [
+    { "entryId": "tweet-1", "content": { "entryType": "TimelineTimelineItem", "itemContent": {
+      "itemType": "TimelineTweet", "tweet_results": { "result": { "rest_id": "1" } } } } },
+    { "entryId": "cursor-bottom-1", "content": {
+      "entryType": "TimelineTimelineCursor", "value": "BOTTOM", "cursorType": "Bottom" } },
+  ] }]));
+  let json = serde_json::to_value(&timeline).unwrap();
+  assert_eq!(json["entries"][0]["content"]["type"], "item");
+  assert_eq!(json["entries"][0]["content"]["value"]["type"], "tweet");
+  assert_eq!(json["bottom_cursor"], "BOTTOM");
+  let parsed: Timeline = serde_json::from_value(json.clone()).unwrap();
+  assert_eq!(serde_json::to_value(parsed).unwrap(), json);
+
+  let playlist = Playlist::Media(MediaPlaylist {
+    target_duration: Some(3),
+    segments: vec![Segment { url: "https://video.twimg.com/1.ts".to_string(), duration: 3.0 }],
+    ended: true,
+    ..Default::default()
+  });
+  let json = serde_json::to_value(&playlist).unwrap();
+  assert_eq!(json["type"], "media");
+  assert_eq!(json["segments"][0]["duration"], 3.0);
+
+  assert_eq!(serde_json::to_value(VideoQuality::MaxBitrate(1000)).unwrap(), json!({ "type": "max_bitrate", "value": 1000 }));
+  assert_eq!(serde_json::to_value(VideoQuality::Best).unwrap(), json!({ "type": "best" }));
+  let file = DownloadedFile {
+    path: PathBuf::from("a/1.jpg"),
+    url: "https://pbs.twimg.com/media/1.jpg".to_string(),
+    status: DownloadStatus::Resumed,
+    len: Some(10),
+    audio: None,
+  };
+  let json = serde_json::to_value(&file).unwrap();
+  assert_eq!((&json["path"], &json["status"]), (&json!("a/1.jpg"), &json!("resumed")));
+  assert_eq!(serde_json::from_value::<DownloadedFile>(json).unwrap().status, DownloadStatus::Resumed);
+}
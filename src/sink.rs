@@ -0,0 +1,247 @@
+//! delivering new tweets from a `Watcher` somewhere else
+//!
+//! a `Sink` receives the new tweets of each source. `WebhookSink` POSTs them
+//! as json to an http endpoint, signing the body with HMAC-SHA256 and retrying
+//! with backoff. deliveries that still fail are appended to a dead-letter file
+
+use std::{
+  error::Error, fmt, fs, io::{self, Write}, path::PathBuf,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use futures_util::future::LocalBoxFuture;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use serde_json::{json, Value};
+use tokio::time::sleep;
+use crate::{
+  types::Tweet,
+  watch::{Watcher, WatchEvent, WatchSource, SinceIdStore},
+};
+
+/// the header holding the signature of the body, e.g.
+/// "sha256=5bdcc146bf60754e6a04..."
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// somewhere new tweets are delivered to
+pub trait Sink {
+  /// deliver the new tweets of a source. an error means the sink has given up
+  /// on them, and they aren't delivered again
+  fn deliver<'a>(&'a mut self, source: &'a WatchSource, tweets: &'a [Tweet]
+  ) -> LocalBoxFuture<'a, Result<(), Box<dyn Error>>>;
+}
+
+/// options for a `WebhookSink`
+#[derive(Debug, Clone)]
+pub struct WebhookOptions {
+  /// key to sign the body with (see `SIGNATURE_HEADER`). unsigned if `None`
+  pub secret: Option<String>,
+  /// number of times to try a delivery before giving up
+  pub max_attempts: u32,
+  /// time to wait after the first failed attempt. doubles after each attempt
+  pub initial_backoff: Duration,
+  /// the longest time to wait between attempts
+  pub max_backoff: Duration,
+  /// file to append deliveries that failed to, as json lines
+  pub dead_letter: Option<PathBuf>,
+}
+
+impl Default for WebhookOptions {
+  fn default() -> WebhookOptions {
+    WebhookOptions {
+      secret: None,
+      max_attempts: 5,
+      initial_backoff: Duration::from_secs(1),
+      max_backoff: Duration::from_secs(60),
+      dead_letter: None,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum WebhookError {
+  Http(reqwest::Error),
+  /// the endpoint responded with a non-2xx status
+  Status(u16),
+  /// writing to the dead-letter file failed
+  Io(io::Error),
+}
+
+impl fmt::Display for WebhookError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      WebhookError::Http(error) => write!(f, "http error: {error}"),
+      WebhookError::Status(status) => write!(f, "webhook responded with status {status}"),
+      WebhookError::Io(error) => write!(f, "io error: {error}"),
+    }
+  }
+}
+
+impl Error for WebhookError {}
+
+impl From<reqwest::Error> for WebhookError {
+  fn from(error: reqwest::Error) -> WebhookError {
+    WebhookError::Http(error)
+  }
+}
+
+impl From<io::Error> for WebhookError {
+  fn from(error: io::Error) -> WebhookError {
+    WebhookError::Io(error)
+  }
+}
+
+impl WebhookError {
+  /// whether trying again might succeed (i.e. not a 4xx other than 429)
+  fn is_retryable(&self) -> bool {
+    match self {
+      WebhookError::Status(status) => *status == 429 || !(400..500).contains(status),
+      _ => true,
+    }
+  }
+}
+
+/// POSTs new tweets as json to a url
+///
+/// the body is `{ "source": { "type": "query", "value": ".." }, "tweets": [..] }`,
+/// with each tweet as `Tweet::to_json()`
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+  url: String,
+  options: WebhookOptions,
+  client: reqwest::Client,
+}
+
+impl WebhookSink {
+  pub fn new(url: &str, options: WebhookOptions) -> WebhookSink {
+    WebhookSink { url: url.to_string(), options, client: reqwest::Client::new() }
+  }
+
+  /// POST the body, retrying failed attempts with backoff. if every attempt
+  /// fails, the body is written to the dead-letter file (if any) and the last
+  /// error is returned
+  pub async fn post(&self, body: &Value) -> Result<(), WebhookError> {
+    let body = body.to_string();
+    let mut backoff = self.options.initial_backoff;
+    let mut attempt = 1;
+    let error = loop {
+      let error = match self.try_post(&body).await {
+        Ok(()) => return Ok(()),
+        Err(error) => error,
+      };
+      if attempt >= self.options.max_attempts || !error.is_retryable() {
+        break error;
+      }
+      sleep(backoff).await;
+      backoff = backoff.saturating_mul(2).min(self.options.max_backoff);
+      attempt += 1;
+    };
+    if let Some(path) = &self.options.dead_letter {
+      let dead_letter = json!({
+        "url": self.url,
+        "time": SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        "attempts": attempt,
+        "error": error.to_string(),
+        "body": body,
+      });
+      let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+      writeln!(file, "{dead_letter}")?;
+    }
+    Err(error)
+  }
+
+  async fn try_post(&self, body: &str) -> Result<(), WebhookError> {
+    let mut request = self.client.post(&self.url)
+      .header("Content-Type", "application/json")
+      .body(body.to_string());
+    if let Some(secret) = &self.options.secret {
+      request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, body.as_bytes())));
+    }
+    let status = request.send().await?.status();
+    if !status.is_success() {
+      return Err(WebhookError::Status(status.as_u16()));
+    }
+    Ok(())
+  }
+}
+
+impl Sink for WebhookSink {
+  fn deliver<'a>(&'a mut self, source: &'a WatchSource, tweets: &'a [Tweet]
+  ) -> LocalBoxFuture<'a, Result<(), Box<dyn Error>>> {
+    Box::pin(async move {
+      let (kind, value) = match source {
+        WatchSource::Query(query) => ("query", query),
+        WatchSource::User(user) => ("user", user),
+      };
+      let body = json!({
+        "source": { "type": kind, "value": value },
+        "tweets": tweets.iter().map(Tweet::to_json).collect::<Vec<Value>>(),
+      });
+      Ok(self.post(&body).await?)
+    })
+  }
+}
+
+/// HMAC-SHA256 of `body`, as lowercase hex
+pub fn sign(secret: &str, body: &[u8]) -> String {
+  // hmac accepts keys of any length
+  let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+  mac.update(body);
+  mac.finalize().into_bytes().iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// poll the watcher forever, delivering the new tweets of each poll to the
+/// sink (see `deliver_events()`). watch errors are retried by the watcher
+///
+/// errors (and gaps) are logged, and polling carries on. this only returns if
+/// the watcher has no sources
+pub async fn run_watcher<S: SinceIdStore>(mut watcher: Watcher<S>, sink: &mut impl Sink) {
+  loop {
+    let events = watcher.poll().await;
+    if events.is_empty() && !watcher.has_sources() {
+      return;
+    }
+    for event in &events {
+      match event {
+        WatchEvent::Error { source, error, retry_in } =>
+          println!("watch error: {error}\n  source: {}, retrying in {retry_in:?}", source.key()),
+        WatchEvent::Gap { source, since_id, until_id } =>
+          println!("watch gap: tweets between {since_id} and {until_id} may be missing\n  source: {}", source.key()),
+        WatchEvent::Tweet { .. } => {},
+      }
+    }
+    for (source, error) in deliver_events(&mut watcher, sink, events).await {
+      println!("delivery error: {error}\n  source: {}", source.key());
+    }
+  }
+}
+
+/// deliver the new tweets of a poll to the sink, one delivery per source, and
+/// save the high-water mark of each source once its delivery is done
+///
+/// a delivery that fails (after any retries by the sink, e.g. once it is in
+/// the dead-letter file) is done too, so its tweets aren't delivered again.
+/// returns the errors of the deliveries (and of saving their marks)
+pub async fn deliver_events<S: SinceIdStore>(watcher: &mut Watcher<S>, sink: &mut impl Sink,
+  events: Vec<WatchEvent>
+) -> Vec<(WatchSource, Box<dyn Error>)> {
+  // group the tweets by source, keeping the order of the sources
+  let mut batches: Vec<(WatchSource, Vec<Tweet>)> = Vec::new();
+  for event in events {
+    if let WatchEvent::Tweet { source, tweet } = event {
+      match batches.iter_mut().find(|(batch_source, _)| *batch_source == source) {
+        Some((_, tweets)) => tweets.push(tweet),
+        None => batches.push((source, vec![tweet])),
+      }
+    }
+  }
+  let mut errors = Vec::new();
+  for (source, tweets) in batches {
+    if let Err(error) = sink.deliver(&source, &tweets).await {
+      errors.push((source.clone(), error));
+    }
+    if let Err(error) = watcher.commit_source(&source) {
+      errors.push((source, Box::new(error) as Box<dyn Error>));
+    }
+  }
+  errors
+}
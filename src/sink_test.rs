@@ -0,0 +1,149 @@
+use std::{
+  cell::RefCell, collections::{HashMap, VecDeque}, error::Error, rc::Rc, sync::{Arc, Mutex}, time::Duration,
+};
+use futures_util::future::LocalBoxFuture;
+use serde_json::Value;
+use crate::{
+  sink::{sign, deliver_events, Sink, WebhookSink, WebhookOptions, WebhookError, SIGNATURE_HEADER},
+  watch::{Watcher, WatcherOptions, WatchSource, SinceIdStore, JsonFileSinceIdStore},
+  snowflake::TweetId,
+  types::Tweet,
+  download_test::{scripted_server, Request},
+  test_fixtures::temp_dir,
+};
+
+/// a webhook endpoint on localhost responding with `statuses` in order (200
+/// once they run out), returning its url and the received requests
+async fn webhook_server(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<Request>>>) {
+  let (base_url, requests) = scripted_server(HashMap::from([("/hook", Vec::new())]), statuses).await;
+  (format!("{base_url}/hook"), requests)
+}
+
+fn options() -> WebhookOptions {
+  WebhookOptions {
+    secret: Some("Jefe".to_string()),
+    initial_backoff: Duration::from_millis(1),
+    ..Default::default()
+  }
+}
+
+#[test]
+fn sink_test_sign() {
+  println!("sign()  //  RFC 4231 test case 2");
+  assert_eq!(
+    sign("Jefe", b"what do ya want for nothing?"),
+    "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+  );
+}
+
+#[tokio::test]
+async fn sink_test_deliver_with_retries() {
+  println!("WebhookSink::deliver()  //  signed json body, retried after 5xx");
+  let (url, requests) = webhook_server(vec![503, 429]).await;
+  let mut sink = WebhookSink::new(&url, options());
+  let tweets = vec![Tweet { id: "1".to_string(), user: "a".to_string(), text: "hi &amp; bye".to_string(), ..Default::default() }];
+  sink.deliver(&WatchSource::Query("from:a".to_string()), &tweets).await.unwrap();
+
+  let requests = requests.lock().unwrap();
+  assert_eq!(requests.len(), 3);
+  let request = &requests[2];
+  assert_eq!(request.header("content-type"), Some("application/json"));
+  let expected_signature = format!("sha256={}", sign("Jefe", request.body.as_bytes()));
+  assert_eq!(request.header(&SIGNATURE_HEADER.to_lowercase()), Some(expected_signature.as_str()));
+  let body: Value = serde_json::from_str(&request.body).unwrap();
+  assert_eq!(body["source"]["type"], "query");
+  assert_eq!(body["source"]["value"], "from:a");
+  assert_eq!(body["tweets"][0]["id"], "1");
+  assert_eq!(body["tweets"][0]["text"], "hi & bye");
+  assert_eq!(body["tweets"][0]["url"], "https://twitter.com/a/status/1");
+}
+
+#[tokio::test]
+async fn sink_test_dead_letter() {
+  println!("WebhookSink::post()  //  failed deliveries go to the dead-letter file");
+  let dir = temp_dir("sink");
+  std::fs::create_dir_all(&dir).unwrap();
+  let dead_letter = dir.join("dead.jsonl");
+  let (url, requests) = webhook_server(vec![500, 500, 500, 400]).await;
+  let options = WebhookOptions { max_attempts: 3, dead_letter: Some(dead_letter.clone()), ..options() };
+  let sink = WebhookSink::new(&url, options);
+
+  // retries until max_attempts
+  let result = sink.post(&serde_json::json!({ "n": 1 })).await;
+  assert!(matches!(result, Err(WebhookError::Status(500))));
+  assert_eq!(requests.lock().unwrap().len(), 3);
+  // a 4xx isn't retried
+  let result = sink.post(&serde_json::json!({ "n": 2 })).await;
+  assert!(matches!(result, Err(WebhookError::Status(400))));
+  assert_eq!(requests.lock().unwrap().len(), 4);
+
+  let lines: Vec<Value> = std::fs::read_to_string(&dead_letter).unwrap().lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect();
+  assert_eq!(lines.len(), 2);
+  assert_eq!((lines[0]["attempts"].as_u64(), lines[0]["body"].as_str()), (Some(3), Some(r#"{"n":1}"#)));
+  assert_eq!((lines[1]["attempts"].as_u64(), lines[1]["body"].as_str()), (Some(1), Some(r#"{"n":2}"#)));
+  assert_eq!(lines[1]["error"], "webhook responded with status 400");
+  std::fs::remove_dir_all(&dir).ok();
+}
+
+/// a sink that fails every delivery of `failing`
+struct FailingSink {
+  failing: WatchSource,
+  delivered: Vec<String>,
+}
+
+impl Sink for FailingSink {
+  fn deliver<'a>(&'a mut self, source: &'a WatchSource, tweets: &'a [Tweet]
+  ) -> LocalBoxFuture<'a, Result<(), Box<dyn Error>>> {
+    Box::pin(async move {
+      if *source == self.failing {
+        return Err("endpoint is down".into());
+      }
+      self.delivered.extend(tweets.iter().map(|tweet| tweet.id.clone()));
+      Ok(())
+    })
+  }
+}
+
+#[tokio::test]
+async fn sink_test_deliver_events_error() {
+  println!("deliver_events()  //  a failed delivery of the second source is reported and its mark saved");
+  let dir = temp_dir("deliver-events");
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("since_ids.json");
+  let (source_a, source_b) = (WatchSource::Query("from:a".to_string()), WatchSource::Query("from:b".to_string()));
+  // query to the tweets of each poll
+  let responses = Rc::new(RefCell::new(HashMap::from([
+    ("from:a".to_string(), VecDeque::from([vec!["5"], vec!["7"]])),
+    ("from:b".to_string(), VecDeque::from([vec!["6"], vec!["8"]])),
+  ])));
+  let store = JsonFileSinceIdStore::open(&path).unwrap();
+  let options = WatcherOptions { interval: Duration::ZERO, ..Default::default() };
+  let mut watcher = Watcher::with_fetch(vec![source_a.clone(), source_b.clone()], store, options, move |query, _| {
+    let query = query.split(" since_id:").next().unwrap().to_string();
+    let ids = responses.borrow_mut().get_mut(&query).unwrap().pop_front().unwrap_or_default();
+    let tweets = ids.into_iter().map(|id| Tweet { id: id.to_string(), ..Default::default() }).collect();
+    async move { Ok((tweets, None)) }
+  });
+  let mut sink = FailingSink { failing: source_b.clone(), delivered: Vec::new() };
+
+  let events = watcher.poll().await;
+  let errors = deliver_events(&mut watcher, &mut sink, events).await;
+  assert_eq!(errors.len(), 1);
+  assert_eq!((&errors[0].0, errors[0].1.to_string()), (&source_b, "endpoint is down".to_string()));
+  assert_eq!(sink.delivered, vec!["5"]);
+  // both batches are handled, so a restart doesn't deliver them again
+  let store = JsonFileSinceIdStore::open(&path).unwrap();
+  assert_eq!(store.get("query:from:a").unwrap(), Some(TweetId(5)));
+  assert_eq!(store.get("query:from:b").unwrap(), Some(TweetId(6)));
+
+  // and polling carries on
+  let events = watcher.poll().await;
+  let errors = deliver_events(&mut watcher, &mut sink, events).await;
+  assert_eq!(errors.len(), 1);
+  assert_eq!(sink.delivered, vec!["5", "7"]);
+  let store = JsonFileSinceIdStore::open(&path).unwrap();
+  assert_eq!(store.get("query:from:b").unwrap(), Some(TweetId(8)));
+  std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,136 @@
+//! tweet ids are twitter "snowflakes": 64 bit numbers made of
+//!
+//! ```text
+//! | 1 bit unused | 41 bits timestamp | 5 bits datacenter | 5 bits worker | 12 bits sequence |
+//! ```
+//!
+//! where the timestamp is the number of milliseconds since the twitter epoch
+//! (2010-11-04T01:42:54.657Z). this means the creation time of a tweet can be
+//! read from its id, and a time can be converted to an id (e.g. to get the
+//! `since_id`/`max_id` of a search for a date range)
+
+use std::{fmt, num::ParseIntError, str::FromStr, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+/// milliseconds between the unix epoch and the twitter epoch
+pub const TWITTER_EPOCH_MS: u64 = 1288834974657;
+
+/// the first snowflake tweet id. tweets before this use sequential ids, so
+/// have no timestamp
+pub const FIRST_SNOWFLAKE_ID: u64 = 29700859247;
+
+const TIMESTAMP_SHIFT: u64 = 22;
+const DATACENTER_SHIFT: u64 = 17;
+const WORKER_SHIFT: u64 = 12;
+
+/// a tweet id (last part of the tweet url), as a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TweetId(pub u64);
+
+impl TweetId {
+  pub fn as_u64(self) -> u64 {
+    self.0
+  }
+
+  /// whether the id is a snowflake (i.e. the tweet was posted after
+  /// 2010-11-04), and so contains a timestamp
+  pub fn is_snowflake(self) -> bool {
+    self.0 >= FIRST_SNOWFLAKE_ID
+  }
+
+  /// creation time of the tweet, in milliseconds since the unix epoch
+  pub fn timestamp_ms(self) -> Option<u64> {
+    match self.is_snowflake() {
+      true => Some((self.0 >> TIMESTAMP_SHIFT) + TWITTER_EPOCH_MS),
+      false => None,
+    }
+  }
+
+  /// creation time of the tweet
+  pub fn timestamp(self) -> Option<SystemTime> {
+    self.timestamp_ms().map(|ms| UNIX_EPOCH + Duration::from_millis(ms))
+  }
+
+  pub fn datacenter_id(self) -> u8 {
+    ((self.0 >> DATACENTER_SHIFT) & 0b11111) as u8
+  }
+
+  pub fn worker_id(self) -> u8 {
+    ((self.0 >> WORKER_SHIFT) & 0b11111) as u8
+  }
+
+  /// the number of ids generated by the same worker in the same millisecond
+  /// before this one
+  pub fn sequence(self) -> u16 {
+    (self.0 & 0xfff) as u16
+  }
+
+  /// the smallest possible id of a tweet posted at `time` (rounded down to the
+  /// millisecond). times before the twitter epoch give the first snowflake id
+  pub fn min_for_timestamp(time: SystemTime) -> TweetId {
+    match twitter_epoch_ms(time) {
+      Some(ms) => TweetId((ms << TIMESTAMP_SHIFT).max(FIRST_SNOWFLAKE_ID)),
+      None => TweetId(FIRST_SNOWFLAKE_ID),
+    }
+  }
+
+  /// the largest possible id of a tweet posted at `time` (rounded down to the
+  /// millisecond)
+  pub fn max_for_timestamp(time: SystemTime) -> TweetId {
+    match twitter_epoch_ms(time) {
+      Some(ms) => TweetId((ms << TIMESTAMP_SHIFT) | ((1 << TIMESTAMP_SHIFT) - 1)),
+      None => TweetId(FIRST_SNOWFLAKE_ID - 1),
+    }
+  }
+}
+
+/// milliseconds since the twitter epoch
+fn twitter_epoch_ms(time: SystemTime) -> Option<u64> {
+  let ms = time.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64;
+  ms.checked_sub(TWITTER_EPOCH_MS)
+}
+
+impl FromStr for TweetId {
+  type Err = ParseIntError;
+
+  fn from_str(id: &str) -> Result<TweetId, ParseIntError> {
+    id.parse::<u64>().map(TweetId)
+  }
+}
+
+impl fmt::Display for TweetId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+/// serialized as a string, see `serde_format`
+#[cfg(feature = "serde")]
+impl serde::Serialize for TweetId {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TweetId {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<TweetId, D::Error> {
+    let id = String::deserialize(deserializer)?;
+    id.parse().map_err(serde::de::Error::custom)
+  }
+}
+
+impl From<u64> for TweetId {
+  fn from(id: u64) -> TweetId {
+    TweetId(id)
+  }
+}
+
+/// get the search operators to only return tweets posted in `[since, until)`
+///
+/// e.g. `format!("from:balajis {}", search_window(since, until))`
+pub fn search_window(since: SystemTime, until: SystemTime) -> String {
+  // `since_id` is exclusive, `max_id` is inclusive
+  let since_id = TweetId::min_for_timestamp(since).0 - 1;
+  let max_id = TweetId::min_for_timestamp(until).0 - 1;
+  format!("since_id:{since_id} max_id:{max_id}")
+}
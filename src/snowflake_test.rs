@@ -0,0 +1,39 @@
+use std::time::{Duration, UNIX_EPOCH};
+use super::snowflake::{TweetId, search_window, FIRST_SNOWFLAKE_ID};
+
+#[test]
+fn snowflake_test_fields() {
+  let id: TweetId = "1513868637307691009".parse().unwrap();
+  assert_eq!(id, TweetId(1513868637307691009));
+  assert_eq!(id.to_string(), "1513868637307691009");
+  // 2022-04-12T13:16:24.110Z
+  assert_eq!(id.timestamp_ms(), Some(1649769384110));
+  assert_eq!(id.timestamp(), Some(UNIX_EPOCH + Duration::from_millis(1649769384110)));
+  assert_eq!(id.datacenter_id(), 10);
+  assert_eq!(id.worker_id(), 6);
+  assert_eq!(id.sequence(), 1);
+
+  // pre-snowflake ids have no timestamp
+  assert_eq!(TweetId(20).timestamp(), None);
+  assert!("abc".parse::<TweetId>().is_err());
+  assert!(TweetId(1513868637307691009) > TweetId(1513376048594882560));
+}
+
+#[test]
+fn snowflake_test_ranges() {
+  let time = UNIX_EPOCH + Duration::from_millis(1649769384110);
+  let min = TweetId::min_for_timestamp(time);
+  let max = TweetId::max_for_timestamp(time);
+  assert_eq!(min, TweetId(1513868637306355712));
+  assert_eq!(max, TweetId(1513868637310550015));
+  assert!(min <= TweetId(1513868637307691009) && TweetId(1513868637307691009) <= max);
+  assert_eq!(min.timestamp(), Some(time));
+  assert_eq!(max.timestamp(), Some(time));
+
+  // before the twitter epoch
+  assert_eq!(TweetId::min_for_timestamp(UNIX_EPOCH), TweetId(FIRST_SNOWFLAKE_ID));
+
+  let until = time + Duration::from_millis(1);
+  assert_eq!(search_window(time, until),
+    "since_id:1513868637306355711 max_id:1513868637310550015");
+}
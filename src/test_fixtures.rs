@@ -0,0 +1,34 @@
+//! factories for the types in `types`, and other helpers shared by the
+//! tests. tests set the fields they care about with struct update syntax, e.g.
+//! `Tweet { text: "hi".to_string(), ..tweet("1", "a") }`
+
+use std::path::PathBuf;
+use crate::types::{Tweet, TweetMedia, MediaKind};
+
+/// a tweet with only an id and a user
+pub(crate) fn tweet(id: &str, user: &str) -> Tweet {
+  Tweet { id: id.to_string(), user: user.to_string(), ..Default::default() }
+}
+
+/// a photo or video with only an id, an image url and a kind
+pub(crate) fn media(id: &str, full_img_url: &str, kind: MediaKind) -> TweetMedia {
+  TweetMedia {
+    id: id.to_string(),
+    shortened_img_url: format!("https://t.co/m{id}"),
+    full_img_url: full_img_url.to_string(),
+    range: None,
+    alt_text: None,
+    original_size: None,
+    crops: Vec::new(),
+    sizes: Vec::new(),
+    kind,
+  }
+}
+
+/// a path in the system temp dir for the test `name`, removed if it exists
+/// (but not created)
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("private-twitter-api-{name}-{}", std::process::id()));
+  std::fs::remove_dir_all(&dir).ok();
+  dir
+}
@@ -0,0 +1,325 @@
+//! parsing of the timeline "instructions" returned by twitter's endpoints
+//!
+//! every timeline-like endpoint (search, `TweetDetail`, `UserTweets`, lists,
+//! etc.) returns an array of instructions that tell the client how to build
+//! the timeline (add these entries, replace that cursor, append these items to
+//! that module, ...). rather than each endpoint hand-walking
+//! `instructions[0]`, they can all be fed into `Timeline::from_instructions()`
+//!
+//! both instruction formats are supported:
+//! - graphql (`{ "type": "TimelineAddEntries", "entries": [..] }`)
+//! - v2 rest, e.g. `2/search/adaptive.json` (`{ "addEntries": { "entries": [..] } }`)
+
+use serde_json::Value;
+
+/// a single timeline instruction
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum TimelineInstruction {
+  /// `TimelineAddEntries` / `addEntries`
+  AddEntries(Vec<TimelineEntry>),
+  /// `TimelineReplaceEntry` / `replaceEntry`
+  ReplaceEntry { entry_id_to_replace: String, entry: TimelineEntry },
+  /// `TimelinePinEntry` / `pinEntry`
+  PinEntry(TimelineEntry),
+  /// `TimelineAddToModule` / `addToModule`
+  AddToModule { module_entry_id: String, items: Vec<ModuleItem>, prepend: bool },
+  /// `TimelineClearCache` / `clearCache`
+  ClearCache,
+  /// any other instruction (e.g. `TimelineTerminateTimeline`), with its raw type
+  Other(String),
+}
+
+/// an entry in the timeline (i.e. an item in `entries`)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimelineEntry {
+  /// e.g. "tweet-1516856286738598375", "conversationthread-1516856...",
+  /// "cursor-bottom-1516856..."
+  pub entry_id: String,
+  pub sort_index: Option<String>,
+  pub content: EntryContent,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum EntryContent {
+  /// `TimelineTimelineItem`
+  Item(TimelineItem),
+  /// `TimelineTimelineModule` (e.g. a reply thread, or the recommended tweets)
+  Module(Vec<ModuleItem>),
+  /// `TimelineTimelineCursor`
+  ///
+  /// note: items whose content is a cursor (e.g. "ShowMoreThreads") are also
+  /// returned as this variant
+  Cursor(TimelineCursor),
+  /// any other entry type, with its raw type
+  Other(String),
+}
+
+/// an item inside a `TimelineTimelineModule`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleItem {
+  pub entry_id: String,
+  pub item: TimelineItem,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum TimelineItem {
+  /// a tweet. the value is the raw `itemContent` for graphql (contains
+  /// `tweet_results`), or the raw `content.tweet` for v2 (contains `id`)
+  Tweet(Value),
+  /// a cursor inside a module (e.g. "Show more" at the end of a thread)
+  Cursor(TimelineCursor),
+  /// any other item type (e.g. `TimelineUser`), with its raw type and content
+  Other { kind: String, content: Value },
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimelineCursor {
+  /// the value to send as the `cursor` variable to get the next page
+  pub value: String,
+  pub cursor_type: CursorType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CursorType {
+  Top,
+  Bottom,
+  /// "Show more" button (e.g. when a thread is too long)
+  ShowMore,
+  /// "Show more replies" button
+  ShowMoreThreads,
+  /// "Show additional replies, including those that may contain offensive content"
+  ShowMoreThreadsPrompt,
+  Other(String),
+}
+
+impl CursorType {
+  fn from_raw(cursor_type: &str) -> CursorType {
+    match cursor_type {
+      "Top" => CursorType::Top,
+      "Bottom" => CursorType::Bottom,
+      "ShowMore" => CursorType::ShowMore,
+      "ShowMoreThreads" => CursorType::ShowMoreThreads,
+      "ShowMoreThreadsPrompt" => CursorType::ShowMoreThreadsPrompt,
+      other => CursorType::Other(other.to_string()),
+    }
+  }
+}
+
+/// the timeline that results from applying all instructions in order
+///
+/// the top and bottom cursors are pulled out of `entries` into their own
+/// fields, so `entries` only contains content (and "show more" cursors)
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timeline {
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub entries: Vec<TimelineEntry>,
+  pub top_cursor: Option<String>,
+  pub bottom_cursor: Option<String>,
+}
+
+impl Timeline {
+  /// build a timeline from a raw `instructions` array
+  pub fn from_instructions(instructions: &Value) -> Timeline {
+    let mut timeline = Timeline::default();
+    for instruction in parse_instructions(instructions) {
+      timeline.apply(instruction);
+    }
+    timeline
+  }
+
+  /// apply a single instruction to the timeline
+  pub fn apply(&mut self, instruction: TimelineInstruction) {
+    match instruction {
+      TimelineInstruction::AddEntries(entries) => {
+        for entry in entries {
+          self.add_entry(entry, false);
+        }
+      },
+      TimelineInstruction::ReplaceEntry { entry_id_to_replace, mut entry } => {
+        // the replacement sometimes has a different id (v2 keeps the same one)
+        entry.entry_id = entry_id_to_replace;
+        self.add_entry(entry, false);
+      },
+      TimelineInstruction::PinEntry(entry) => self.add_entry(entry, true),
+      TimelineInstruction::AddToModule { module_entry_id, mut items, prepend } => {
+        let module = self.entries.iter_mut()
+          .find(|e| e.entry_id == module_entry_id)
+          .and_then(|e| match &mut e.content {
+            EntryContent::Module(module_items) => Some(module_items),
+            _ => None,
+          });
+        match module {
+          Some(module_items) if prepend => {
+            items.append(module_items);
+            *module_items = items;
+          },
+          Some(module_items) => module_items.append(&mut items),
+          // when fetching with a "show more" cursor, the module being added
+          // to is from the previous response, so add it as a new module
+          None => self.entries.push(TimelineEntry {
+            entry_id: module_entry_id,
+            sort_index: None,
+            content: EntryContent::Module(items),
+          }),
+        }
+      },
+      TimelineInstruction::ClearCache => self.entries.clear(),
+      TimelineInstruction::Other(_) => {},
+    }
+  }
+
+  /// get an entry by its `entryId`
+  pub fn entry(&self, entry_id: &str) -> Option<&TimelineEntry> {
+    self.entries.iter().find(|e| e.entry_id == entry_id)
+  }
+
+  fn add_entry(&mut self, entry: TimelineEntry, pin: bool) {
+    if let EntryContent::Cursor(cursor) = &entry.content {
+      match cursor.cursor_type {
+        CursorType::Top => {
+          self.top_cursor = Some(cursor.value.clone());
+          return;
+        },
+        CursorType::Bottom => {
+          self.bottom_cursor = Some(cursor.value.clone());
+          return;
+        },
+        _ => {},
+      }
+    }
+    // entries with an existing id replace the existing entry
+    match self.entries.iter().position(|e| e.entry_id == entry.entry_id) {
+      Some(i) => self.entries[i] = entry,
+      None if pin => self.entries.insert(0, entry),
+      None => self.entries.push(entry),
+    }
+  }
+}
+
+/// parse a raw `instructions` array into typed instructions
+pub fn parse_instructions(instructions: &Value) -> Vec<TimelineInstruction> {
+  let instructions = match instructions.as_array() {
+    Some(instructions) => instructions,
+    None => return Vec::new(),
+  };
+  instructions.iter().map(parse_instruction).collect()
+}
+
+fn parse_instruction(instruction: &Value) -> TimelineInstruction {
+  // graphql has a `type` key, v2 has a single key named after the type,
+  // containing the body
+  let (kind, body) = match instruction["type"].as_str() {
+    Some(kind) => (kind.to_string(), instruction),
+    None => match instruction.as_object().and_then(|o| o.iter().next()) {
+      Some((key, body)) => (key.clone(), body),
+      None => return TimelineInstruction::Other("".to_string()),
+    },
+  };
+  match kind.as_str() {
+    "TimelineAddEntries" | "addEntries" => TimelineInstruction::AddEntries(
+      body["entries"].as_array().map(|entries| {
+        entries.iter().map(parse_entry).collect()
+      }).unwrap_or_default()
+    ),
+    "TimelineReplaceEntry" | "replaceEntry" => TimelineInstruction::ReplaceEntry {
+      entry_id_to_replace: body["entry_id_to_replace"].as_str()
+        .or(body["entryIdToReplace"].as_str())
+        .unwrap_or_default().to_string(),
+      entry: parse_entry(&body["entry"]),
+    },
+    "TimelinePinEntry" | "pinEntry" => TimelineInstruction::PinEntry(
+      parse_entry(&body["entry"])
+    ),
+    "TimelineAddToModule" | "addToModule" => TimelineInstruction::AddToModule {
+      module_entry_id: body["moduleEntryId"].as_str().unwrap_or_default().to_string(),
+      items: parse_module_items(&body["moduleItems"]),
+      prepend: body["prepend"].as_bool().unwrap_or(false),
+    },
+    "TimelineClearCache" | "clearCache" => TimelineInstruction::ClearCache,
+    _ => TimelineInstruction::Other(kind),
+  }
+}
+
+fn parse_entry(entry: &Value) -> TimelineEntry {
+  let entry_id = entry["entryId"].as_str().unwrap_or_default().to_string();
+  let sort_index = entry["sortIndex"].as_str().map(|s| s.to_string());
+  let content = &entry["content"];
+
+  let content = match content["entryType"].as_str().or(content["__typename"].as_str()) {
+    /* -------------------------------- graphql -------------------------------- */
+    Some("TimelineTimelineItem") => match parse_item(content) {
+      TimelineItem::Cursor(cursor) => EntryContent::Cursor(cursor),
+      item => EntryContent::Item(item),
+    },
+    Some("TimelineTimelineModule") => EntryContent::Module(
+      parse_module_items(&content["items"])
+    ),
+    Some("TimelineTimelineCursor") => EntryContent::Cursor(parse_cursor(content)),
+    Some(other) => EntryContent::Other(other.to_string()),
+    /* ----------------------------------- v2 ----------------------------------- */
+    None => {
+      if let Some(cursor) = content["operation"].get("cursor") {
+        EntryContent::Cursor(parse_cursor(cursor))
+      } else if content.get("item").is_some() {
+        EntryContent::Item(parse_item(&content["item"]))
+      } else if let Some(module) = content.get("timelineModule") {
+        EntryContent::Module(parse_module_items(&module["items"]))
+      } else {
+        EntryContent::Other("".to_string())
+      }
+    },
+  };
+  TimelineEntry { entry_id, sort_index, content }
+}
+
+fn parse_module_items(items: &Value) -> Vec<ModuleItem> {
+  match items.as_array() {
+    Some(items) => items.iter().map(|item| ModuleItem {
+      entry_id: item["entryId"].as_str().unwrap_or_default().to_string(),
+      item: parse_item(&item["item"]),
+    }).collect(),
+    None => Vec::new(),
+  }
+}
+
+/// parse the content of an item (the `content` of a `TimelineTimelineItem`
+/// entry, or the `item` of a module item)
+fn parse_item(item: &Value) -> TimelineItem {
+  /* --------------------------------- graphql --------------------------------- */
+  if let Some(item_content) = item.get("itemContent") {
+    let kind = item_content["itemType"].as_str()
+      .or(item_content["__typename"].as_str())
+      .unwrap_or_default();
+    return match kind {
+      "TimelineTweet" => TimelineItem::Tweet(item_content.clone()),
+      "TimelineTimelineCursor" => TimelineItem::Cursor(parse_cursor(item_content)),
+      _ => TimelineItem::Other { kind: kind.to_string(), content: item_content.clone() },
+    };
+  }
+  /* ----------------------------------- v2 ----------------------------------- */
+  // e.g. `{ "content": { "tweet": { "id": "..", "displayType": "Tweet" } } }`
+  match item["content"].as_object().and_then(|o| o.iter().next()) {
+    Some((kind, content)) if kind == "tweet" => TimelineItem::Tweet(content.clone()),
+    Some((kind, content)) => TimelineItem::Other { kind: kind.clone(), content: content.clone() },
+    None => TimelineItem::Other { kind: "".to_string(), content: item.clone() },
+  }
+}
+
+fn parse_cursor(cursor: &Value) -> TimelineCursor {
+  TimelineCursor {
+    value: cursor["value"].as_str().unwrap_or_default().to_string(),
+    cursor_type: CursorType::from_raw(cursor["cursorType"].as_str().unwrap_or_default()),
+  }
+}
@@ -0,0 +1,118 @@
+use serde_json::json;
+use super::timeline::{Timeline, EntryContent, TimelineItem, CursorType};
+
+#[test]
+fn timeline_test_graphql_entries() {
+  println!("Timeline::from_instructions()  //  TweetDetail entries, module, and cursors");
+  let instructions = json!([
+    { "type": "TimelineClearCache" },
+    { "type": "TimelineAddEntries", "entries": [
+      { "entryId": "cursor-top-1", "sortIndex": "3", "content": {
+        "entryType": "TimelineTimelineCursor", "value": "TOP", "cursorType": "Top" } },
+      { "entryId": "tweet-1", "sortIndex": "2", "content": {
+        "entryType": "TimelineTimelineItem", "itemContent": {
+          "itemType": "TimelineTweet", "tweet_results": { "result": { "rest_id": "1" } } } } },
+      { "entryId": "conversationthread-2", "sortIndex": "1", "content": {
+        "entryType": "TimelineTimelineModule", "items": [
+          { "entryId": "conversationthread-2-tweet-2", "item": { "itemContent": {
+            "itemType": "TimelineTweet", "tweet_results": { "result": { "rest_id": "2" } } } } },
+          { "entryId": "conversationthread-2-cursor-showmore-3", "item": { "itemContent": {
+            "itemType": "TimelineTimelineCursor", "value": "MORE", "cursorType": "ShowMore" } } },
+        ] } },
+      { "entryId": "cursor-showmorethreads-4", "sortIndex": "0", "content": {
+        "entryType": "TimelineTimelineItem", "itemContent": {
+          "itemType": "TimelineTimelineCursor", "value": "THREADS", "cursorType": "ShowMoreThreads" } } },
+      { "entryId": "cursor-bottom-5", "sortIndex": "0", "content": {
+        "entryType": "TimelineTimelineCursor", "value": "BOTTOM", "cursorType": "Bottom" } },
+    ] },
+    { "type": "TimelineTerminateTimeline", "direction": "Top" },
+  ]);
+  let timeline = Timeline::from_instructions(&instructions);
+
+  assert_eq!(timeline.top_cursor.as_deref(), Some("TOP"));
+  assert_eq!(timeline.bottom_cursor.as_deref(), Some("BOTTOM"));
+  assert_eq!(timeline.entries.len(), 3);
+  assert!(matches!(&timeline.entries[0].content,
+    EntryContent::Item(TimelineItem::Tweet(t)) if t["tweet_results"]["result"]["rest_id"] == "1"));
+  match &timeline.entries[1].content {
+    EntryContent::Module(items) => {
+      assert_eq!(items.len(), 2);
+      assert!(matches!(&items[1].item,
+        TimelineItem::Cursor(c) if c.cursor_type == CursorType::ShowMore && c.value == "MORE"));
+    },
+    other => panic!("expected module, got {other:?}"),
+  }
+  assert!(matches!(&timeline.entries[2].content,
+    EntryContent::Cursor(c) if c.cursor_type == CursorType::ShowMoreThreads));
+}
+
+#[test]
+fn timeline_test_graphql_add_to_module() {
+  println!("Timeline::apply()  //  \"show more\" items appended/prepended to a module");
+  let mut timeline = Timeline::from_instructions(&json!([
+    { "type": "TimelineAddEntries", "entries": [
+      { "entryId": "conversationthread-1", "content": {
+        "entryType": "TimelineTimelineModule", "items": [
+          { "entryId": "conversationthread-1-tweet-1", "item": { "itemContent": {
+            "itemType": "TimelineTweet", "tweet_results": {} } } },
+        ] } },
+    ] },
+  ]));
+  for instruction in super::timeline::parse_instructions(&json!([
+    { "type": "TimelineAddToModule", "moduleEntryId": "conversationthread-1", "prepend": false,
+      "moduleItems": [ { "entryId": "conversationthread-1-tweet-2", "item": { "itemContent": {
+        "itemType": "TimelineTweet", "tweet_results": {} } } } ] },
+    { "type": "TimelineAddToModule", "moduleEntryId": "conversationthread-1", "prepend": true,
+      "moduleItems": [ { "entryId": "conversationthread-1-tweet-0", "item": { "itemContent": {
+        "itemType": "TimelineTweet", "tweet_results": {} } } } ] },
+    // module from a previous response
+    { "type": "TimelineAddToModule", "moduleEntryId": "conversationthread-9", "prepend": false,
+      "moduleItems": [ { "entryId": "conversationthread-9-tweet-10", "item": { "itemContent": {
+        "itemType": "TimelineTweet", "tweet_results": {} } } } ] },
+  ])) {
+    timeline.apply(instruction);
+  }
+
+  assert_eq!(timeline.entries.len(), 2);
+  match &timeline.entries[0].content {
+    EntryContent::Module(items) => assert_eq!(
+      items.iter().map(|i| i.entry_id.as_str()).collect::<Vec<_>>(),
+      ["conversationthread-1-tweet-0", "conversationthread-1-tweet-1", "conversationthread-1-tweet-2"]
+    ),
+    other => panic!("expected module, got {other:?}"),
+  }
+  assert_eq!(timeline.entries[1].entry_id, "conversationthread-9");
+}
+
+#[test]
+fn timeline_test_v2_search() {
+  println!("Timeline::from_instructions()  //  v2 search entries, replaced and pinned entries");
+  let instructions = json!([
+    { "addEntries": { "entries": [
+      { "entryId": "tweet-2", "sortIndex": "2", "content": { "item": {
+        "content": { "tweet": { "id": "2", "displayType": "Tweet" } } } } },
+      { "entryId": "user-3", "sortIndex": "1", "content": { "item": {
+        "content": { "user": { "id": "3" } } } } },
+      { "entryId": "sq-cursor-top", "sortIndex": "9", "content": { "operation": {
+        "cursor": { "value": "TOP", "cursorType": "Top" } } } },
+      { "entryId": "sq-cursor-bottom", "sortIndex": "0", "content": { "operation": {
+        "cursor": { "value": "BOTTOM", "cursorType": "Bottom" } } } },
+    ] } },
+    { "replaceEntry": { "entryIdToReplace": "sq-cursor-bottom", "entry": {
+      "entryId": "sq-cursor-bottom", "sortIndex": "0", "content": { "operation": {
+        "cursor": { "value": "BOTTOM2", "cursorType": "Bottom" } } } } } },
+    { "pinEntry": { "entry": {
+      "entryId": "tweet-1", "sortIndex": "3", "content": { "item": {
+        "content": { "tweet": { "id": "1", "displayType": "Tweet" } } } } } } },
+  ]);
+  let timeline = Timeline::from_instructions(&instructions);
+
+  assert_eq!(timeline.top_cursor.as_deref(), Some("TOP"));
+  assert_eq!(timeline.bottom_cursor.as_deref(), Some("BOTTOM2"));
+  let ids = timeline.entries.iter().map(|e| e.entry_id.as_str()).collect::<Vec<_>>();
+  assert_eq!(ids, ["tweet-1", "tweet-2", "user-3"]);
+  assert!(matches!(&timeline.entries[1].content,
+    EntryContent::Item(TimelineItem::Tweet(t)) if t["id"] == "2"));
+  assert!(matches!(&timeline.entries[2].content,
+    EntryContent::Item(TimelineItem::Other { kind, .. }) if kind == "user"));
+}
@@ -0,0 +1,122 @@
+//! merging the results of many searches into one timeline
+//!
+//! e.g. a feed of several accounts, with one `from:user` query per account
+
+use std::{cmp::Reverse, collections::HashMap, error::Error, future::Future, time::SystemTime};
+use futures_util::{stream, StreamExt};
+use crate::types::Tweet;
+use super::query::query_to_tweets;
+
+type QueryResult = Result<Vec<Tweet>, Box<dyn Error>>;
+
+/// options for `aggregate_queries()`
+#[derive(Debug, Clone)]
+pub struct AggregateOptions {
+  /// max number of queries fetched at once
+  pub concurrency: usize,
+}
+
+impl Default for AggregateOptions {
+  fn default() -> AggregateOptions {
+    AggregateOptions { concurrency: 4 }
+  }
+}
+
+/// a tweet of an aggregated feed
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AggregatedTweet {
+  pub tweet: Tweet,
+  /// the queries that returned the tweet (in the order they were given)
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub queries: Vec<String>,
+}
+
+impl AggregatedTweet {
+  /// when the tweet was last posted or retweeted, which is what the feed is
+  /// sorted by
+  pub fn time(&self) -> Option<SystemTime> {
+    let retweet_times = self.tweet.retweeted_by.iter().flatten()
+      .filter_map(|retweet| retweet.created_at);
+    self.tweet.created_at.into_iter().chain(retweet_times).max()
+  }
+}
+
+/// a query that failed
+#[derive(Debug)]
+pub struct QueryFailure {
+  pub query: String,
+  pub error: Box<dyn Error>,
+}
+
+/// the merged results of several queries
+#[derive(Debug, Default)]
+pub struct AggregatedFeed {
+  /// tweets without duplicates, newest first (see `AggregatedTweet::time()`)
+  pub tweets: Vec<AggregatedTweet>,
+  /// the queries that failed (in the order they were given). the tweets of the
+  /// other queries are still returned
+  pub failures: Vec<QueryFailure>,
+}
+
+/// run the search queries (at most `options.concurrency` at once) and merge
+/// their tweets into one timeline
+///
+/// tweets returned by more than one query are merged (see `Tweet::merge()`),
+/// so e.g. a tweet retweeted by two of the users is listed once with both
+/// retweets
+pub async fn aggregate_queries(queries: &[&str], options: &AggregateOptions) -> AggregatedFeed {
+  aggregate_queries_with(queries, options, |query| async move {
+    query_to_tweets(&query).await
+  }).await
+}
+
+/// `aggregate_queries()`, using `fetch` to run each query
+pub async fn aggregate_queries_with<F, Fut>(queries: &[&str], options: &AggregateOptions, fetch: F
+) -> AggregatedFeed
+where
+  F: Fn(String) -> Fut,
+  Fut: Future<Output = QueryResult>,
+{
+  let fetch = &fetch;
+  let mut results: Vec<(usize, QueryResult)> = stream::iter(queries.iter().enumerate())
+    .map(|(i, query)| async move { (i, fetch(query.to_string()).await) })
+    .buffer_unordered(options.concurrency.max(1))
+    .collect()
+    .await;
+  // merge in the order the queries were given, rather than the order they
+  // finished, so the result doesn't depend on timing
+  results.sort_by_key(|(i, _)| *i);
+
+  let mut feed = AggregatedFeed::default();
+  // tweet id to index in `feed.tweets`
+  let mut indices: HashMap<String, usize> = HashMap::new();
+  for (i, result) in results {
+    let query = queries[i].to_string();
+    let tweets = match result {
+      Ok(tweets) => tweets,
+      Err(error) => {
+        feed.failures.push(QueryFailure { query, error });
+        continue;
+      },
+    };
+    for tweet in tweets {
+      match indices.get(&tweet.id) {
+        Some(&index) => {
+          let aggregated = &mut feed.tweets[index];
+          aggregated.tweet.merge(&tweet);
+          if !aggregated.queries.contains(&query) {
+            aggregated.queries.push(query.clone());
+          }
+        },
+        None => {
+          indices.insert(tweet.id.clone(), feed.tweets.len());
+          feed.tweets.push(AggregatedTweet { tweet, queries: vec![query.clone()] });
+        },
+      }
+    }
+  }
+  // newest first. `None` (unknown time) sorts last
+  feed.tweets.sort_by_key(|tweet| Reverse(tweet.time()));
+  feed
+}
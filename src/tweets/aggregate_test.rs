@@ -0,0 +1,54 @@
+use std::{error::Error, time::{Duration, UNIX_EPOCH}};
+use crate::{types::{Tweet, Retweet}, test_fixtures::tweet};
+use super::aggregate::{aggregate_queries_with, AggregateOptions};
+
+fn tweet_at(id: &str, user: &str, secs: u64) -> Tweet {
+  Tweet { created_at: Some(UNIX_EPOCH + Duration::from_secs(secs)), ..tweet(id, user) }
+}
+
+fn retweeted(mut tweet: Tweet, user: &str, id: &str, secs: u64) -> Tweet {
+  tweet.retweeted_by = Some(vec![Retweet {
+    user: user.to_string(),
+    id: id.to_string(),
+    created_at: Some(UNIX_EPOCH + Duration::from_secs(secs)),
+  }]);
+  tweet
+}
+
+#[tokio::test]
+async fn aggregate_test_merge_and_sort() {
+  println!("aggregate_queries_with()  //  dedupes, merges retweets and quotes, sorts, reports failures");
+  let fetch = |query: String| async move {
+    match query.as_str() {
+      "from:a" => Ok(vec![
+        tweet_at("1", "a", 100),
+        retweeted(tweet_at("3", "c", 50), "a", "30", 400),
+      ]),
+      "from:b" => {
+        let mut quote = tweet_at("2", "b", 200);
+        quote.quoted_id = Some("1".to_string());
+        quote.quote = Some(Box::new(tweet_at("1", "a", 100)));
+        Ok(vec![retweeted(tweet_at("3", "c", 50), "b", "31", 300), quote])
+      },
+      "from:c" => Ok(vec![tweet_at("3", "c", 50)]),
+      _ => Err(Box::<dyn Error>::from("rate limited")),
+    }
+  };
+  let queries = ["from:a", "from:x", "from:b", "from:c"];
+  let options = AggregateOptions { concurrency: 2 };
+  let feed = aggregate_queries_with(&queries, &options, fetch).await;
+
+  // "3" was last retweeted at 400, so is newest
+  let ids: Vec<&str> = feed.tweets.iter().map(|t| t.tweet.id.as_str()).collect();
+  assert_eq!(ids, vec!["3", "2", "1"]);
+  assert_eq!(feed.tweets[0].queries, vec!["from:a", "from:b", "from:c"]);
+  let retweeters: Vec<&str> = feed.tweets[0].tweet.retweeted_by.as_ref().unwrap().iter()
+    .map(|retweet| retweet.user.as_str()).collect();
+  assert_eq!(retweeters, vec!["a", "b"]);
+  assert_eq!(feed.tweets[1].tweet.quote.as_ref().map(|quote| quote.id.as_str()), Some("1"));
+  assert_eq!(feed.tweets[2].queries, vec!["from:a"]);
+
+  assert_eq!(feed.failures.len(), 1);
+  assert_eq!(feed.failures[0].query, "from:x");
+  assert_eq!(feed.failures[0].error.to_string(), "rate limited");
+}
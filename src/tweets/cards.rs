@@ -0,0 +1,136 @@
+use serde_json::Value;
+use crate::{
+  types::{TweetCard, SummaryCard, PollCard, PollChoice, PlayerCard, UnifiedCard, CardApp, CardImage},
+  date::parse_iso8601,
+};
+
+/// the `binding_values` of a card, which are a map in v2 (search), and an
+/// array of `{ "key": .., "value": .. }` in graphql (`card.legacy`)
+struct Bindings<'a> {
+  json: &'a Value,
+}
+
+impl<'a> Bindings<'a> {
+  fn get(&self, key: &str) -> Option<&'a Value> {
+    match self.json {
+      Value::Array(bindings) => bindings.iter()
+        .find(|binding| binding["key"].as_str() == Some(key))
+        .map(|binding| &binding["value"]),
+      Value::Object(bindings) => bindings.get(key),
+      _ => None,
+    }
+  }
+
+  fn string(&self, key: &str) -> Option<String> {
+    self.get(key)?["string_value"].as_str().map(|s| s.to_string())
+  }
+
+  /// numbers (e.g. vote counts) are stored as strings
+  fn number(&self, key: &str) -> Option<u64> {
+    self.string(key)?.parse().ok()
+  }
+
+  fn boolean(&self, key: &str) -> Option<bool> {
+    self.get(key)?["boolean_value"].as_bool()
+  }
+
+  /// the first of the image keys that is present
+  fn image(&self, keys: &[&str]) -> Option<CardImage> {
+    keys.iter().find_map(|key| {
+      let image = &self.get(key)?["image_value"];
+      Some(CardImage {
+        url: image["url"].as_str()?.to_string(),
+        width: image["width"].as_u64(),
+        height: image["height"].as_u64(),
+      })
+    })
+  }
+}
+
+/// parse the `card` of a tweet (v2), or the `card` of a graphql tweet result
+pub fn parse_card(json: &Value) -> Option<TweetCard> {
+  // graphql nests the card in `legacy`
+  let card = match json.get("legacy") {
+    Some(legacy) => legacy,
+    None => json,
+  };
+  let name = card["name"].as_str()?;
+  let bindings = Bindings { json: &card["binding_values"] };
+  // the name can be prefixed with an id, e.g. "745291183405076480:broadcast"
+  let kind = name.rsplit(':').next().unwrap_or(name);
+
+  let parsed = match kind {
+    "summary" | "summary_large_image" => TweetCard::Summary(SummaryCard {
+      large_image: kind == "summary_large_image",
+      title: bindings.string("title"),
+      description: bindings.string("description"),
+      image: bindings.image(&[
+        "photo_image_full_size_original", "summary_photo_image_original", "thumbnail_image_original",
+      ]),
+      domain: bindings.string("domain").or_else(|| bindings.string("vanity_url")),
+      url: bindings.string("card_url").or_else(|| card["url"].as_str().map(|s| s.to_string())),
+    }),
+    "player" => TweetCard::Player(PlayerCard {
+      title: bindings.string("title"),
+      description: bindings.string("description"),
+      player_url: bindings.string("player_url"),
+      player_size: bindings.number("player_width").zip(bindings.number("player_height")),
+      image: bindings.image(&["player_image_original", "player_image"]),
+      url: bindings.string("card_url").or_else(|| card["url"].as_str().map(|s| s.to_string())),
+    }),
+    "unified_card" => TweetCard::Unified(parse_unified_card(&bindings.string("unified_card")?)?),
+    // e.g. "poll2choice_text_only", "poll4choice_image", "poll3choice_video"
+    _ if kind.starts_with("poll") && kind.contains("choice") => {
+      let choices = (1..=4)
+        .map_while(|i| Some(PollChoice {
+          label: bindings.string(&format!("choice{i}_label"))?,
+          count: bindings.number(&format!("choice{i}_count")).unwrap_or(0),
+        }))
+        .collect();
+      TweetCard::Poll(PollCard {
+        choices,
+        end_time: bindings.string("end_datetime_utc").and_then(|date| parse_iso8601(&date)),
+        last_updated: bindings.string("last_updated_datetime_utc").and_then(|date| parse_iso8601(&date)),
+        duration_minutes: bindings.number("duration_minutes"),
+        counts_are_final: bindings.boolean("counts_are_final").unwrap_or(false),
+        image: bindings.image(&["image_original", "image_large"]),
+      })
+    },
+    _ => TweetCard::Other { name: name.to_string() },
+  };
+  Some(parsed)
+}
+
+/// parse the json string of a `unified_card`
+fn parse_unified_card(unified_card: &str) -> Option<UnifiedCard> {
+  let json: Value = serde_json::from_str(unified_card).ok()?;
+  let components = json["component_objects"].as_object();
+  let title = components.into_iter().flatten()
+    .filter(|(_, component)| component["type"] == "details")
+    .find_map(|(_, component)| component["data"]["title"]["content"].as_str())
+    .map(|s| s.to_string());
+  let urls = json["destination_objects"].as_object().into_iter().flatten()
+    .filter_map(|(_, destination)| destination["data"]["url_data"]["url"].as_str())
+    .map(|s| s.to_string())
+    .collect();
+  let apps = json["app_store_data"].as_object().into_iter().flatten()
+    .flat_map(|(_, apps)| apps.as_array().into_iter().flatten())
+    .filter_map(|app| Some(CardApp {
+      id: app["id"].as_str()?.to_string(),
+      store: app["type"].as_str().unwrap_or_default().to_string(),
+      title: app["title"]["content"].as_str().map(|s| s.to_string()),
+      category: app["category"]["content"].as_str().map(|s| s.to_string()),
+    }))
+    .collect();
+  let media_urls = json["media_entities"].as_object().into_iter().flatten()
+    .filter_map(|(_, media)| media["media_url_https"].as_str())
+    .map(|s| s.to_string())
+    .collect();
+  Some(UnifiedCard {
+    kind: json["type"].as_str().unwrap_or_default().to_string(),
+    title,
+    urls,
+    apps,
+    media_urls,
+  })
+}
@@ -0,0 +1,128 @@
+use std::time::{Duration, UNIX_EPOCH};
+use serde_json::json;
+use crate::types::{TweetCard, PollChoice, CardApp};
+use super::cards::parse_card;
+
+fn string(value: &str) -> serde_json::Value {
+  json!({ "type": "STRING", "string_value": value })
+}
+
+#[test]
+fn cards_test_summary() {
+  println!("parse_card()  //  v2 summary_large_image");
+  let card = parse_card(&json!({
+    "name": "summary_large_image",
+    "url": "https://t.co/abc",
+    "binding_values": {
+      "title": string("A title"),
+      "description": string("A description"),
+      "domain": string("example.com"),
+      "card_url": string("https://t.co/abc"),
+      "summary_photo_image_original": { "type": "IMAGE", "image_value": {
+        "url": "https://pbs.twimg.com/card_img/1/a?format=jpg&name=orig", "width": 1200, "height": 630,
+      } },
+    },
+  })).unwrap();
+  let summary = match card {
+    TweetCard::Summary(summary) => summary,
+    card => panic!("not a summary card: {card:?}"),
+  };
+  assert!(summary.large_image);
+  assert_eq!(summary.title.as_deref(), Some("A title"));
+  assert_eq!(summary.description.as_deref(), Some("A description"));
+  assert_eq!(summary.domain.as_deref(), Some("example.com"));
+  assert_eq!(summary.url.as_deref(), Some("https://t.co/abc"));
+  let image = summary.image.unwrap();
+  assert_eq!((image.width, image.height), (Some(1200), Some(630)));
+}
+
+#[test]
+fn cards_test_poll() {
+  println!("parse_card()  //  graphql poll3choice_text_only");
+  let card = parse_card(&json!({ "rest_id": "card://1", "legacy": {
+    "name": "poll3choice_text_only",
+    "binding_values": [
+      { "key": "choice1_label", "value": string("yes") },
+      { "key": "choice1_count", "value": string("120") },
+      { "key": "choice2_label", "value": string("no") },
+      { "key": "choice2_count", "value": string("80") },
+      { "key": "choice3_label", "value": string("maybe") },
+      { "key": "choice3_count", "value": string("0") },
+      { "key": "end_datetime_utc", "value": string("2022-04-14T12:00:00Z") },
+      { "key": "last_updated_datetime_utc", "value": string("2022-04-14T12:00:01Z") },
+      { "key": "duration_minutes", "value": string("1440") },
+      { "key": "counts_are_final", "value": { "type": "BOOLEAN", "boolean_value": true } },
+    ],
+  } })).unwrap();
+  let poll = match card {
+    TweetCard::Poll(poll) => poll,
+    card => panic!("not a poll card: {card:?}"),
+  };
+  assert_eq!(poll.choices, [
+    PollChoice { label: "yes".to_string(), count: 120 },
+    PollChoice { label: "no".to_string(), count: 80 },
+    PollChoice { label: "maybe".to_string(), count: 0 },
+  ]);
+  assert_eq!(poll.total_votes(), 200);
+  assert_eq!(poll.end_time, Some(UNIX_EPOCH + Duration::from_secs(1649937600)));
+  assert_eq!(poll.last_updated, Some(UNIX_EPOCH + Duration::from_secs(1649937601)));
+  assert_eq!(poll.duration_minutes, Some(1440));
+  assert!(poll.counts_are_final);
+}
+
+#[test]
+fn cards_test_player_unified_other() {
+  let card = parse_card(&json!({
+    "name": "player",
+    "binding_values": {
+      "title": string("video"),
+      "player_url": string("https://www.youtube.com/embed/abc"),
+      "player_width": string("1280"),
+      "player_height": string("720"),
+    },
+  })).unwrap();
+  match card {
+    TweetCard::Player(player) => {
+      assert_eq!(player.player_url.as_deref(), Some("https://www.youtube.com/embed/abc"));
+      assert_eq!(player.player_size, Some((1280, 720)));
+    },
+    card => panic!("not a player card: {card:?}"),
+  }
+
+  let unified_card = json!({
+    "type": "image_carousel_app",
+    "component_objects": {
+      "details_1": { "type": "details", "data": { "title": { "content": "An app" } } },
+    },
+    "destination_objects": {
+      "browser_1": { "type": "browser", "data": { "url_data": { "url": "https://example.com", "vanity": "example.com" } } },
+    },
+    "app_store_data": { "app_1": [
+      { "type": "android_app", "id": "com.example", "title": { "content": "An app" }, "category": { "content": "Games" } },
+    ] },
+    "media_entities": { "13_1": { "media_url_https": "https://pbs.twimg.com/media/a.jpg" } },
+  });
+  let card = parse_card(&json!({
+    "name": "unified_card",
+    "binding_values": { "unified_card": string(&unified_card.to_string()) },
+  })).unwrap();
+  match card {
+    TweetCard::Unified(unified) => {
+      assert_eq!(unified.kind, "image_carousel_app");
+      assert_eq!(unified.title.as_deref(), Some("An app"));
+      assert_eq!(unified.urls, ["https://example.com"]);
+      assert_eq!(unified.apps, [CardApp {
+        id: "com.example".to_string(),
+        store: "android_app".to_string(),
+        title: Some("An app".to_string()),
+        category: Some("Games".to_string()),
+      }]);
+      assert_eq!(unified.media_urls, ["https://pbs.twimg.com/media/a.jpg"]);
+    },
+    card => panic!("not a unified card: {card:?}"),
+  }
+
+  assert!(matches!(parse_card(&json!({ "name": "745291183405076480:broadcast", "binding_values": {} })),
+    Some(TweetCard::Other { name }) if name == "745291183405076480:broadcast"));
+  assert!(parse_card(&json!(null)).is_none());
+}
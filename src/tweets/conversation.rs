@@ -0,0 +1,337 @@
+use std::{collections::{HashMap, HashSet, VecDeque}, error::Error, future::Future};
+use serde_json::Value;
+use tokio::time::{sleep, Duration};
+use crate::{
+  fetch::id_fetch,
+  types::{Tweet, TweetItem, Conversation, ConversationNode},
+  timeline::{Timeline, TimelineEntry, EntryContent, TimelineItem, CursorType},
+  tweets::url::timeline_item_to_tweet_item,
+};
+
+/// a parsed tweet (or tombstone), along with the id of the tweet it is 
+/// replying to
+pub(crate) struct Reply {
+  pub item: TweetItem,
+  pub in_reply_to: Option<String>,
+}
+
+/// a `TweetDetail` timeline split into the tweets above the focal tweet, the
+/// focal tweet, and the replies
+type Detail = (Timeline, Vec<Reply>, Reply, Vec<Reply>);
+
+/// fetch the `TweetDetail` instructions of a tweet (at a cursor, or "" for the
+/// first page)
+async fn detail_fetch(tweet_id: String, cursor: String) -> Result<Value, Box<dyn Error>> {
+  id_fetch(&tweet_id, &cursor, false).await
+}
+
+/// get the full conversation of a tweet as a tree: the chain of tweets above
+/// it, the tweet itself, and every reply below it (including the replies
+/// hidden behind "show more replies"/"show more" buttons)
+///
+/// unlike `url_to_tweets()`, this doesn't try to guess which tweets are part
+/// of the author's thread, so all replies are returned
+pub async fn conversation(tweet_id: &str) -> Result<Conversation, Box<dyn Error>> {
+  let (timeline, mut ancestors, focal, mut replies) = load_detail(tweet_id, &detail_fetch).await?;
+
+  let focal_tweet = focal.item.as_tweet().ok_or("focal tweet can't be viewed")?.clone();
+  let mut seen_tweet_ids = replies.iter()
+    .filter_map(|reply| reply.item.id().map(|id| id.to_string()))
+    .chain([focal_tweet.id.clone()])
+    .collect::<HashSet<String>>();
+
+  // keep loading replies until there are no cursors left
+  let mut cursors: VecDeque<String> = reply_cursors(&timeline);
+  let mut seen_cursors: HashSet<String> = HashSet::new();
+  while let Some(cursor) = cursors.pop_front() {
+    if !seen_cursors.insert(cursor.clone()) {
+      continue;
+    }
+    sleep(Duration::from_millis(200)).await; // wait between requests
+    let timeline = Timeline::from_instructions(&detail_fetch(tweet_id.to_string(), cursor).await?);
+
+    replies.extend(timeline_replies(&timeline.entries).into_iter()
+      .filter(|reply| is_unseen(reply, &mut seen_tweet_ids)));
+    cursors.extend(reply_cursors(&timeline));
+  }
+
+  load_ancestors(tweet_id, &timeline, &mut ancestors, &focal, &detail_fetch).await?;
+
+  Ok(Conversation {
+    ancestors: ancestors.into_iter().map(|reply| reply.item).collect(),
+    focal: focal_tweet,
+    replies: reply_tree(replies),
+  })
+}
+
+/// get the chain of tweets above a tweet, starting with the root of the
+/// conversation and ending with the tweet the given tweet is replying to
+///
+/// deleted or otherwise unavailable tweets in the chain are returned as
+/// `TweetItem::Tombstone`. if twitter cuts off the chain, the tweets above the
+/// cut-off are loaded with extra requests
+pub async fn ancestors(tweet_id: &str) -> Result<Vec<TweetItem>, Box<dyn Error>> {
+  ancestors_with(tweet_id, &detail_fetch).await
+}
+
+/// `ancestors()`, using `fetch` to get the `TweetDetail` instructions of a
+/// tweet at a cursor
+pub(crate) async fn ancestors_with<F, Fut>(tweet_id: &str, fetch: &F) -> Result<Vec<TweetItem>, Box<dyn Error>>
+where
+  F: Fn(String, String) -> Fut,
+  Fut: Future<Output = Result<Value, Box<dyn Error>>>,
+{
+  let (timeline, mut ancestors, focal, _) = load_detail(tweet_id, fetch).await?;
+  load_ancestors(tweet_id, &timeline, &mut ancestors, &focal, fetch).await?;
+  Ok(ancestors.into_iter().map(|reply| reply.item).collect())
+}
+
+/// load and split the `TweetDetail` timeline of a tweet
+async fn load_detail<F, Fut>(tweet_id: &str, fetch: &F) -> Result<Detail, Box<dyn Error>>
+where
+  F: Fn(String, String) -> Fut,
+  Fut: Future<Output = Result<Value, Box<dyn Error>>>,
+{
+  let timeline = Timeline::from_instructions(&fetch(tweet_id.to_string(), String::new()).await?);
+  Ok(split_detail(timeline, tweet_id).ok_or("focal tweet is missing from the conversation")?)
+}
+
+/// split the `TweetDetail` timeline of a tweet into the tweets above the focal
+/// tweet, the focal tweet, and the replies (`None` if the focal tweet isn't in
+/// the timeline, e.g. because it was deleted)
+fn split_detail(timeline: Timeline, tweet_id: &str) -> Option<Detail> {
+  // everything before the focal tweet are its ancestors, and everything after
+  // it are replies
+  let focal_index = timeline.entries.iter()
+    .position(|entry| entry.entry_id.strip_prefix("tweet-") == Some(tweet_id))?;
+  let ancestors = timeline_replies(&timeline.entries[..focal_index]);
+  let focal = timeline_replies(&timeline.entries[focal_index..=focal_index]).pop()?;
+  let replies = timeline_replies(&timeline.entries[(focal_index + 1)..]);
+
+  Some((timeline, ancestors, focal, replies))
+}
+
+/// complete the chain of `ancestors` loaded from the `TweetDetail` timeline of
+/// `tweet_id`
+///
+/// first the top cursors are followed, then if the top tweet is still a reply
+/// (twitter only returns so many ancestors), the `TweetDetail` of the tweet it
+/// is replying to is loaded, and so on until the root of the conversation. a
+/// parent that isn't in its own `TweetDetail` (e.g. it was deleted, or the
+/// account is protected) is added as a tombstone, and ends the chain
+async fn load_ancestors<F, Fut>(tweet_id: &str, timeline: &Timeline, ancestors: &mut Vec<Reply>,
+  focal: &Reply, fetch: &F
+) -> Result<(), Box<dyn Error>>
+where
+  F: Fn(String, String) -> Fut,
+  Fut: Future<Output = Result<Value, Box<dyn Error>>>,
+{
+  let mut seen_tweet_ids = ancestors.iter().chain([focal])
+    .filter_map(|reply| reply.item.id().map(|id| id.to_string()))
+    .collect::<HashSet<String>>();
+
+  let mut detail_id = tweet_id.to_string();
+  let mut top_cursor = timeline.top_cursor.clone();
+  loop {
+    while let Some(cursor) = top_cursor.take() {
+      sleep(Duration::from_millis(200)).await; // wait between requests
+      let timeline = Timeline::from_instructions(&fetch(detail_id.clone(), cursor.clone()).await?);
+      let mut above: Vec<Reply> = timeline_replies(&timeline.entries).into_iter()
+        .filter(|reply| is_unseen(reply, &mut seen_tweet_ids))
+        .collect();
+      if above.is_empty() {
+        break;
+      }
+      above.append(ancestors);
+      *ancestors = above;
+      top_cursor = timeline.top_cursor.filter(|next_cursor| *next_cursor != cursor);
+    }
+
+    // tombstones don't have a parent id, so the chain can't be followed past
+    // them
+    let top = ancestors.first().unwrap_or(focal);
+    let parent_id = match &top.in_reply_to {
+      Some(parent_id) if top.item.as_tweet().is_some() && !seen_tweet_ids.contains(parent_id) => parent_id.clone(),
+      _ => return Ok(()),
+    };
+
+    sleep(Duration::from_millis(200)).await; // wait between requests
+    let timeline = Timeline::from_instructions(&fetch(parent_id.clone(), String::new()).await?);
+    let (timeline, mut above, parent, _) = match split_detail(timeline, &parent_id) {
+      Some(detail) => detail,
+      None => {
+        let tombstone = TweetItem::Tombstone { text: "This Tweet is unavailable.".to_string(), id: Some(parent_id) };
+        ancestors.insert(0, Reply { item: tombstone, in_reply_to: None });
+        return Ok(());
+      },
+    };
+    above.retain(|reply| is_unseen(reply, &mut seen_tweet_ids));
+    seen_tweet_ids.insert(parent_id.clone());
+    above.push(parent);
+    above.append(ancestors);
+    *ancestors = above;
+
+    detail_id = parent_id;
+    top_cursor = timeline.top_cursor;
+  }
+}
+
+/// get the cursors of a timeline that load more replies
+fn reply_cursors(timeline: &Timeline) -> VecDeque<String> {
+  let mut cursors = VecDeque::new();
+  if let Some(bottom_cursor) = &timeline.bottom_cursor {
+    cursors.push_back(bottom_cursor.clone());
+  }
+  for entry in &timeline.entries {
+    match &entry.content {
+      EntryContent::Cursor(cursor) if cursor.cursor_type != CursorType::Top => {
+        cursors.push_back(cursor.value.clone());
+      },
+      EntryContent::Module(items) => {
+        for module_item in items {
+          if let TimelineItem::Cursor(cursor) = &module_item.item {
+            cursors.push_back(cursor.value.clone());
+          }
+        }
+      },
+      _ => {},
+    }
+  }
+  cursors
+}
+
+/// whether the reply hasn't been seen yet, adding its id to `seen_tweet_ids`
+///
+/// tombstones without an id can't be checked for duplicates, so are always 
+/// unseen
+fn is_unseen(reply: &Reply, seen_tweet_ids: &mut HashSet<String>) -> bool {
+  match reply.item.id() {
+    Some(id) => seen_tweet_ids.insert(id.to_string()),
+    None => true,
+  }
+}
+
+/// parse all tweets (and tombstones) in the timeline entries, along with the 
+/// id of the tweet each is replying to
+///
+/// tombstones don't have an `in_reply_to_status_id_str`, so they are treated
+/// as replying to the tweet above them in the same module
+pub(crate) fn timeline_replies(entries: &[TimelineEntry]) -> Vec<Reply> {
+  let mut replies = Vec::new();
+  for entry in entries {
+    match &entry.content {
+      EntryContent::Item(item @ TimelineItem::Tweet(_)) => {
+        match timeline_item_to_tweet_item(item, &entry.entry_id) {
+          Some(TweetItem::Tweet(tweet)) => {
+            let in_reply_to = tweet.in_reply_to_status_id.clone();
+            replies.push(Reply { item: TweetItem::Tweet(tweet), in_reply_to });
+          },
+          Some(tombstone @ TweetItem::Tombstone { .. }) => {
+            replies.push(Reply { item: tombstone, in_reply_to: None });
+          },
+          _ => {},
+        }
+      },
+      EntryContent::Module(items) => {
+        let mut prev_id: Option<String> = None;
+        for module_item in items {
+          match timeline_item_to_tweet_item(&module_item.item, &module_item.entry_id) {
+            Some(TweetItem::Tweet(tweet)) => {
+              let in_reply_to = tweet.in_reply_to_status_id.clone().or_else(|| prev_id.clone());
+              prev_id = Some(tweet.id.clone());
+              replies.push(Reply { item: TweetItem::Tweet(tweet), in_reply_to });
+            },
+            Some(tombstone @ TweetItem::Tombstone { .. }) => {
+              let in_reply_to = prev_id.clone();
+              if let Some(id) = tombstone.id() {
+                prev_id = Some(id.to_string());
+              }
+              replies.push(Reply { item: tombstone, in_reply_to });
+            },
+            _ => {},
+          }
+        }
+      },
+      _ => {},
+    }
+  }
+  replies
+}
+
+/// link tweets into reply trees by their `in_reply_to_status_id`, e.g. to 
+/// rebuild the conversations in search results without extra requests
+///
+/// tweets replying to tweets that aren't in `tweets` (or that aren't replies)
+/// are returned as the top level of the trees, in the same order as `tweets`.
+/// so are tweets whose replies loop back to them, see `reply_tree()`
+pub fn tweets_to_reply_tree(tweets: Vec<Tweet>) -> Vec<ConversationNode> {
+  reply_tree(tweets.into_iter()
+    .map(|tweet| Reply { in_reply_to: tweet.in_reply_to_status_id.clone(), item: TweetItem::Tweet(tweet) })
+    .collect())
+}
+
+/// link the replies into a tree by their `in_reply_to`, keeping the order the
+/// tweets were loaded in
+///
+/// replies to tweets that aren't in `replies` (i.e. the focal tweet) are
+/// returned as the top level of the tree. so are replies that form a cycle
+/// (e.g. 2 is a reply to 3, and 3 a reply to 2), cut at one of them, so no
+/// reply is lost
+///
+/// the tree is built without recursion, so a very deep thread can't overflow
+/// the stack
+pub(crate) fn reply_tree(replies: Vec<Reply>) -> Vec<ConversationNode> {
+  let index_of: HashMap<&str, usize> = replies.iter().enumerate()
+    .filter_map(|(i, reply)| reply.item.id().map(|id| (id, i)))
+    .collect();
+
+  let mut roots: Vec<usize> = Vec::new();
+  let mut parents: Vec<Option<usize>> = vec![None; replies.len()];
+  let mut children: Vec<Vec<usize>> = vec![Vec::new(); replies.len()];
+  for (i, reply) in replies.iter().enumerate() {
+    match reply.in_reply_to.as_deref().and_then(|id| index_of.get(id)) {
+      Some(&parent) if parent != i => {
+        parents[i] = Some(parent);
+        children[parent].push(i);
+      },
+      _ => roots.push(i),
+    }
+  }
+
+  let mut items: Vec<Option<TweetItem>> = replies.into_iter().map(|reply| Some(reply.item)).collect();
+  let mut nodes: Vec<Option<ConversationNode>> = items.iter().map(|_| None).collect();
+  let mut visited = vec![false; items.len()];
+  let mut walked = vec![false; items.len()];
+  let mut tops: Vec<usize> = Vec::new();
+  // cycles can't be reached from a root, so are only started from once every 
+  // root has been visited
+  for i in roots.into_iter().chain(0..items.len()) {
+    if visited[i] {
+      continue;
+    }
+    // every reply left has a parent, so walk up to one on the cycle
+    let mut start = i;
+    while !walked[start] {
+      walked[start] = true;
+      start = parents[start].unwrap_or(start);
+    }
+    tops.push(start);
+    let mut order: Vec<usize> = Vec::new();
+    let mut stack = vec![start];
+    while let Some(i) = stack.pop() {
+      if !visited[i] {
+        visited[i] = true;
+        order.push(i);
+        stack.extend(children[i].iter().rev());
+      }
+    }
+    // children come after their parent in `order`, so build from the end. the
+    // reply looping back to `start` finds it not built yet, so is cut there
+    for &i in order.iter().rev() {
+      let replies = children[i].iter().filter_map(|&child| nodes[child].take()).collect();
+      nodes[i] = items[i].take().map(|item| ConversationNode { item, replies });
+    }
+  }
+  tops.sort_unstable();
+  tops.into_iter().filter_map(|i| nodes[i].take()).collect()
+}
@@ -0,0 +1,185 @@
+use std::{collections::HashMap, error::Error};
+use serde_json::{json, Value};
+use crate::{timeline::Timeline, types::{ConversationNode, Tweet, TweetItem}};
+use super::conversation::{
+  conversation, ancestors, ancestors_with, timeline_replies, reply_tree, tweets_to_reply_tree,
+};
+
+fn tweet_item(id: &str, user: &str, in_reply_to: Option<&str>) -> Value {
+  json!({ "item": { "itemContent": {
+    "itemType": "TimelineTweet",
+    "tweet_results": { "result": {
+      "__typename": "Tweet",
+      "core": { "user_results": { "result": { "legacy": { "screen_name": user } } } },
+      "legacy": { "id_str": id, "full_text": format!("tweet {id}"), "in_reply_to_status_id_str": in_reply_to },
+    } },
+  } } })
+}
+
+fn tombstone_item() -> Value {
+  json!({ "item": { "itemContent": {
+    "itemType": "TimelineTweet",
+    "tweet_results": { "result": {
+      "__typename": "TweetTombstone",
+      "tombstone": { "text": { "text": "This Tweet was deleted by the Tweet author. Learn more" } },
+    } },
+  } } })
+}
+
+/// the `TweetDetail` instructions of a tweet: `(id, in_reply_to)` of each
+/// tweet, and the top cursor
+fn detail(tweets: &[(&str, Option<&str>)], top_cursor: Option<&str>) -> Value {
+  let mut entries: Vec<Value> = tweets.iter().map(|(id, in_reply_to)| {
+    let mut entry = tweet_item(id, "A", *in_reply_to)["item"].take();
+    entry["entryType"] = json!("TimelineTimelineItem");
+    json!({ "entryId": format!("tweet-{id}"), "content": entry })
+  }).collect();
+  if let Some(top_cursor) = top_cursor {
+    entries.insert(0, json!({ "entryId": "cursor-top", "content": {
+      "entryType": "TimelineTimelineCursor", "value": top_cursor, "cursorType": "Top" } }));
+  }
+  json!([{ "type": "TimelineAddEntries", "entries": entries }])
+}
+
+/// fetch `TweetDetail`s from `details`, by `(tweet id, cursor)`, erroring for
+/// any that aren't in it
+async fn fetch_detail(details: &HashMap<(&str, &str), Value>, tweet_id: String, cursor: String
+) -> Result<Value, Box<dyn Error>> {
+  details.get(&(tweet_id.as_str(), cursor.as_str())).cloned().ok_or_else(|| "network error".into())
+}
+
+fn ids(nodes: &[ConversationNode]) -> Vec<&str> {
+  nodes.iter().filter_map(|node| node.item.id()).collect()
+}
+
+#[test]
+fn conversation_test_reply_tree() {
+  println!("reply_tree()  //  replies linked by in_reply_to, tombstone linked to tweet above it");
+  let module = |entry_id: &str, items: Vec<Value>| json!({
+    "entryId": entry_id,
+    "content": { "entryType": "TimelineTimelineModule", "items": items },
+  });
+  let timeline = Timeline::from_instructions(&json!([{ "type": "TimelineAddEntries", "entries": [
+    module("conversationthread-2", vec![
+      tweet_item("2", "B", Some("1")),
+      tweet_item("3", "C", Some("2")),
+      tombstone_item(),
+    ]),
+    module("conversationthread-4", vec![
+      tweet_item("4", "D", Some("1")),
+    ]),
+    // reply to a reply in another module
+    module("conversationthread-5", vec![
+      tweet_item("5", "A", Some("2")),
+    ]),
+  ] }]));
+
+  let replies = timeline_replies(&timeline.entries);
+  assert_eq!(replies.len(), 5);
+  let tree = reply_tree(replies);
+
+  assert_eq!(ids(&tree), ["2", "4"]);
+  assert_eq!(ids(&tree[0].replies), ["3", "5"]);
+  assert_eq!(tree[0].replies[0].replies.len(), 1);
+  assert!(matches!(&tree[0].replies[0].replies[0].item,
+    TweetItem::Tombstone { text, id: None } if text == "This Tweet was deleted by the Tweet author."));
+  assert!(tree[1].replies.is_empty());
+}
+
+#[test]
+fn conversation_test_tweets_to_reply_tree() {
+  println!("tweets_to_reply_tree()  //  search results, out of order");
+  let tweet = |id: &str, in_reply_to: Option<&str>| Tweet {
+    id: id.to_string(),
+    in_reply_to_status_id: in_reply_to.map(|id| id.to_string()),
+    ..Default::default()
+  };
+  let tree = tweets_to_reply_tree(vec![
+    tweet("3", Some("2")),
+    tweet("2", Some("1")),
+    tweet("1", None),
+    tweet("5", Some("4")), // parent isn't in the results
+  ]);
+  assert_eq!(ids(&tree), ["1", "5"]);
+  assert_eq!(ids(&tree[0].replies), ["2"]);
+  assert_eq!(ids(&tree[0].replies[0].replies), ["3"]);
+}
+
+#[test]
+fn conversation_test_reply_tree_cycles() {
+  println!("tweets_to_reply_tree()  //  replies in a cycle aren't lost, deep threads don't overflow the stack");
+  let tweet = |id: &str, in_reply_to: Option<&str>| Tweet {
+    id: id.to_string(),
+    in_reply_to_status_id: in_reply_to.map(|id| id.to_string()),
+    ..Default::default()
+  };
+  let tree = tweets_to_reply_tree(vec![
+    tweet("4", Some("3")),
+    tweet("1", None),
+    tweet("2", Some("3")),
+    tweet("3", Some("2")),
+  ]);
+  assert_eq!(ids(&tree), ["1", "3"]);
+  assert_eq!(ids(&tree[1].replies), ["4", "2"]);
+
+  let depth: usize = 100_000;
+  let thread: Vec<Tweet> = (0..depth)
+    .map(|i| tweet(&i.to_string(), i.checked_sub(1).map(|parent| parent.to_string()).as_deref()))
+    .collect();
+  let tree = tweets_to_reply_tree(thread);
+  let mut node = &tree[0];
+  let mut count = 1;
+  while let Some(reply) = node.replies.first() {
+    node = reply;
+    count += 1;
+  }
+  assert_eq!(count, depth);
+}
+
+#[tokio::test]
+async fn conversation_test_ancestors_chain() {
+  println!("ancestors_with()  //  top cursor, parent details, deleted root, cycle, errors");
+  let details = HashMap::from([
+    (("5", ""), detail(&[("4", Some("3")), ("5", Some("4"))], Some("TOP"))),
+    (("5", "TOP"), detail(&[("3", Some("2"))], Some("TOP"))),
+    (("2", ""), detail(&[("2", Some("1"))], None)),
+    // 1 was deleted, so isn't in its own detail
+    (("1", ""), json!([])),
+  ]);
+  let ancestors = ancestors_with("5", &|id, cursor| fetch_detail(&details, id, cursor)).await.unwrap();
+  let ids: Vec<Option<&str>> = ancestors.iter().map(|item| item.id()).collect();
+  assert_eq!(ids, [Some("1"), Some("2"), Some("3"), Some("4")]);
+  assert!(matches!(&ancestors[0], TweetItem::Tombstone { .. }));
+
+  // a parent that's already in the chain isn't loaded again
+  let details = HashMap::from([
+    (("3", ""), detail(&[("2", Some("3")), ("3", Some("2"))], None)),
+  ]);
+  let ancestors = ancestors_with("3", &|id, cursor| fetch_detail(&details, id, cursor)).await.unwrap();
+  assert_eq!(ancestors.len(), 1);
+
+  // failing to load a parent is an error, not the end of the chain
+  let details = HashMap::from([
+    (("3", ""), detail(&[("3", Some("2"))], None)),
+  ]);
+  let result = ancestors_with("3", &|id, cursor| fetch_detail(&details, id, cursor)).await;
+  assert_eq!(result.unwrap_err().to_string(), "network error");
+}
+
+#[tokio::test]
+async fn conversation_test_1() {
+  println!("conversation()  //  reply with ancestors and replies");
+  let conversation = conversation("1514815632511963144").await.unwrap();
+  assert_eq!(conversation.focal.id, "1514815632511963144");
+  assert!(!conversation.ancestors.is_empty());
+  assert!(!conversation.replies.is_empty());
+}
+
+#[tokio::test]
+async fn conversation_test_ancestors() {
+  println!("ancestors()  //  reply, thread, last-tweet");
+  let ancestors = ancestors("1514816123677540355").await.unwrap();
+  assert!(ancestors.len() > 1);
+  // the root of the conversation is visible (not a tombstone)
+  assert!(ancestors[0].as_tweet().is_some());
+}
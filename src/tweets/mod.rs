@@ -1,8 +1,29 @@
 pub mod query;
 pub mod url;
+pub mod conversation;
+pub mod tweet_ref;
+pub mod text;
+pub mod aggregate;
+pub mod stats;
 mod parsing;
+mod cards;
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow, clippy::len_zero)]
 mod query_test;
 #[cfg(test)]
-mod url_test;
\ No newline at end of file
+mod url_test;
+#[cfg(test)]
+mod conversation_test;
+#[cfg(test)]
+mod tweet_ref_test;
+#[cfg(test)]
+mod parsing_test;
+#[cfg(test)]
+mod text_test;
+#[cfg(test)]
+mod cards_test;
+#[cfg(test)]
+mod aggregate_test;
+#[cfg(test)]
+mod stats_test;
@@ -1,5 +1,108 @@
 use serde_json::Value;
-use crate::types::{TweetURLs, TweetMedia};
+use crate::{
+  types::{
+    Tweet, TweetAuthor, TweetURLs, TweetMetrics, TweetEntities, TextRange, Hashtag, Mention, Cashtag,
+    TweetMedia, MediaKind, VideoInfo, VideoVariant, MediaSize, MediaCrop,
+  },
+  date::parse_twitter_date,
+  tweets::cards::parse_card,
+};
+
+/// convert a legacy tweet object into a `Tweet`. this is the `legacy` object 
+/// of graphql tweets (`TweetDetail`), and the tweet objects of v2 
+/// (`globalObjects.tweets` of search), which share the same format
+/// 
+/// `user` is the username of the author, and `views_json` is the object 
+/// containing the view count (see `parse_metrics()`), as both are stored 
+/// outside of the legacy object. `author`, `quote` and `retweeted_by` are left 
+/// empty, as they are stored differently by each endpoint (as is `card` for 
+/// graphql)
+pub fn parse_legacy_tweet(json: &Value, user: &str, views_json: &Value) -> Tweet {
+  let str_field = |key: &str| json[key].as_str().map(|s| s.to_string());
+  Tweet {
+    id: json["id_str"].as_str().unwrap().to_string(),
+    user: user.to_string(),
+    author: None,
+    text: json["full_text"].as_str().unwrap().to_string(),
+    display_text_range: TextRange::from_indices(&json["display_text_range"]),
+    entities: parse_entities(json),
+    created_at: json["created_at"].as_str().and_then(parse_twitter_date),
+    media: parse_media(json),
+    urls: parse_urls(json),
+    quote: None,
+    quoted_id: str_field("quoted_status_id_str"),
+    thread_id: json["self_thread"]["id_str"].as_str().map(|s| s.to_string()),
+    in_reply_to_status_id: str_field("in_reply_to_status_id_str"),
+    in_reply_to_user_id: str_field("in_reply_to_user_id_str"),
+    in_reply_to_screen_name: str_field("in_reply_to_screen_name"),
+    reply_mentions: parse_reply_mentions(json),
+    conversation_id: str_field("conversation_id_str"),
+    lang: str_field("lang"),
+    metrics: Some(parse_metrics(json, views_json)),
+    card: parse_card(&json["card"]),
+    retweeted_by: None,
+  }
+}
+
+/// get the display name and avatar from a user object (the `legacy` object of 
+/// graphql users, or the user objects of v2)
+pub fn parse_author(user_json: &Value) -> Option<TweetAuthor> {
+  Some(TweetAuthor {
+    name: user_json["name"].as_str()?.to_string(),
+    avatar_url: user_json["profile_image_url_https"].as_str().map(|s| s.to_string()),
+  })
+}
+
+/// get the usernames a reply is addressed to
+///
+/// these are the mentions before the start of `display_text_range` (the part 
+/// of the text twitter shows). if there's no `display_text_range`, the 
+/// leading "@user" mentions of the text are used instead
+pub fn parse_reply_mentions(json: &Value) -> Option<Vec<String>> {
+  json["in_reply_to_status_id_str"].as_str()?;
+  let display_start = json["display_text_range"][0].as_u64();
+  let mentions = match (display_start, json["entities"]["user_mentions"].as_array()) {
+    (Some(display_start), Some(mentions_json)) => mentions_json.iter()
+      .filter(|mention| mention["indices"][1].as_u64().map_or(false, |end| end <= display_start))
+      .filter_map(|mention| mention["screen_name"].as_str())
+      .map(|name| name.to_string())
+      .collect(),
+    _ => json["full_text"].as_str().unwrap_or_default()
+      .split_whitespace()
+      .map_while(|word| word.strip_prefix('@'))
+      .filter(|name| !name.is_empty())
+      .map(|name| name.to_string())
+      .collect(),
+  };
+  Some(mentions)
+}
+
+/// get the hashtags, mentions and cashtags in `entities`
+pub fn parse_entities(json: &Value) -> TweetEntities {
+  let entities = &json["entities"];
+  // entities of a kind, skipping any without a text or valid indices
+  fn parse_all<T>(entities_json: &Value, key: &str, parse: impl Fn(String, &Value, TextRange) -> T) -> Vec<T> {
+    entities_json.as_array().into_iter().flatten()
+      .filter_map(|entity| {
+        let text = entity[key].as_str()?.to_string();
+        let range = TextRange::from_indices(&entity["indices"])?;
+        Some(parse(text, entity, range))
+      }).collect()
+  }
+  TweetEntities {
+    hashtags: parse_all(&entities["hashtags"], "text",
+      |text, _, range| Hashtag { text, range }),
+    mentions: parse_all(&entities["user_mentions"], "screen_name",
+      |screen_name, mention, range| Mention {
+        screen_name,
+        name: mention["name"].as_str().map(|s| s.to_string()),
+        id: mention["id_str"].as_str().map(|s| s.to_string()),
+        range,
+      }),
+    cashtags: parse_all(&entities["symbols"], "text",
+      |text, _, range| Cashtag { text, range }),
+  }
+}
 
 pub fn parse_urls(json: &Value) -> Option<Vec<TweetURLs>> {
   match json["entities"]["urls"].as_array() {
@@ -9,6 +112,8 @@ pub fn parse_urls(json: &Value) -> Option<Vec<TweetURLs>> {
           let item = TweetURLs {
               shortened_url: url_json["url"].as_str().unwrap().to_string(),
               full_url: url_json["expanded_url"].as_str().unwrap().to_string(),
+              display_url: url_json["display_url"].as_str().unwrap_or_default().to_string(),
+              range: TextRange::from_indices(&url_json["indices"]),
           };
           urls.push(item);
       }
@@ -19,47 +124,82 @@ pub fn parse_urls(json: &Value) -> Option<Vec<TweetURLs>> {
 }
 
 pub fn parse_media(json: &Value) -> Option<Vec<TweetMedia>> {
-  if let Some(media_json) = json["extended_entities"]["media"].as_array() {
-    let mut media: Vec<TweetMedia> = Vec::new();
+  let media_json = json["extended_entities"]["media"].as_array()?;
+  let str_field = |item: &Value, key: &str| item[key].as_str().unwrap_or_default().to_string();
+  let media = media_json.iter().map(|item| {
+    let original_info = &item["original_info"];
+    let original_size = match (original_info["width"].as_u64(), original_info["height"].as_u64()) {
+      (Some(width), Some(height)) => Some((width, height)),
+      _ => None,
+    };
+    let crops = original_info["focus_rects"].as_array().into_iter().flatten()
+      .filter_map(|rect| Some(MediaCrop {
+        x: rect["x"].as_u64()?,
+        y: rect["y"].as_u64()?,
+        width: rect["w"].as_u64()?,
+        height: rect["h"].as_u64()?,
+      })).collect();
+    let sizes = item["sizes"].as_object().into_iter().flatten()
+      .filter_map(|(name, size)| Some(MediaSize {
+        name: name.clone(),
+        width: size["w"].as_u64()?,
+        height: size["h"].as_u64()?,
+        resize: str_field(size, "resize"),
+      })).collect();
+    let kind = match item["type"].as_str() {
+      Some("video") => MediaKind::Video(parse_video_info(&item["video_info"])),
+      Some("animated_gif") => MediaKind::AnimatedGif(parse_video_info(&item["video_info"])),
+      _ => MediaKind::Photo,
+    };
+    TweetMedia {
+      id: str_field(item, "id_str"),
+      shortened_img_url: str_field(item, "url"),
+      full_img_url: str_field(item, "media_url_https"),
+      range: TextRange::from_indices(&item["indices"]),
+      // v2 (search) uses `ext_alt_text`, graphql sometimes uses `alt_text`
+      alt_text: item["ext_alt_text"].as_str().or_else(|| item["alt_text"].as_str())
+        .map(|s| s.to_string()),
+      original_size,
+      crops,
+      sizes,
+      kind,
+    }
+  }).collect();
+  Some(media)
+}
 
-    for item in media_json {
-      let shortened_img_url = item["url"].as_str().unwrap().to_string();
-      let full_img_url = item["media_url_https"].as_str().unwrap().to_string();
-      let kind = item["type"].as_str().unwrap().to_string(); // i.e. photo or video
+fn parse_video_info(json: &Value) -> VideoInfo {
+  let aspect_ratio = match (json["aspect_ratio"][0].as_u64(), json["aspect_ratio"][1].as_u64()) {
+    (Some(width), Some(height)) => Some((width, height)),
+    _ => None,
+  };
+  let variants = json["variants"].as_array().into_iter().flatten()
+    .filter_map(|variant| Some(VideoVariant {
+      url: variant["url"].as_str()?.to_string(),
+      content_type: variant["content_type"].as_str().unwrap_or_default().to_string(),
+      bitrate: variant["bitrate"].as_u64(),
+    })).collect();
+  VideoInfo {
+    duration_ms: json["duration_millis"].as_u64(),
+    aspect_ratio,
+    variants,
+  }
+}
 
-      let mut video_url: Option<String> = None;
-      if kind == "video" {
-        // sort array by bitrate so that the highest bitrate variant is first in 
-        // the array. the .m3u8` variant doesn't have a bitrate property, so must 
-        // use `?? -1` to push it to the end of the array
-        let variants = item["video_info"]["variants"].as_array().unwrap();
-        let mut highest_bitrate = 0;
-        let mut highest_bitrate_mp4_url = "";
-        for variant in variants {
-          // need default val bc one of the variants never has a bitrate val
-          let bitrate = variant["bitrate"].as_i64().unwrap_or(0);
-          if bitrate > highest_bitrate {
-            highest_bitrate = bitrate;
-            highest_bitrate_mp4_url = variant["url"].as_str().unwrap();
-          }
-        }
-        video_url = Some(highest_bitrate_mp4_url.to_string());
-      } else if kind == "animated_gif" {
-        // only one entry in the variants array for gifs
-        video_url = Some(
-          item["video_info"]["variants"][0]["url"].as_str().unwrap().to_string()
-        );
-      }
-      let media_item = TweetMedia {
-        shortened_img_url,
-        full_img_url,
-        kind,
-        video_url,
-      };
-      media.push(media_item);
-    }
-    Some(media)
-  } else {
-    None
+/// get the engagement counts from a tweet's `legacy` object (graphql) or tweet
+/// object (v2)
+///
+/// views are stored outside of the legacy object, so are passed separately
+/// (graphql `views`, v2 `ext.views.r.ok`)
+pub fn parse_metrics(json: &Value, views_json: &Value) -> TweetMetrics {
+  TweetMetrics {
+    retweets: json["retweet_count"].as_u64().unwrap_or(0),
+    replies: json["reply_count"].as_u64().unwrap_or(0),
+    quotes: json["quote_count"].as_u64().unwrap_or(0),
+    faves: json["favorite_count"].as_u64().unwrap_or(0),
+    bookmarks: json["bookmark_count"].as_u64(),
+    // graphql returns the count as a string
+    views: views_json["count"].as_u64()
+      .or_else(|| views_json["count"].as_str().and_then(|s| s.parse().ok())),
   }
 }
@@ -0,0 +1,221 @@
+use serde_json::json;
+use crate::{timeline::{Timeline, EntryContent, CursorType}, types::{TweetItem, TweetMetrics, MediaKind, MediaCrop}};
+use super::{parsing::{parse_legacy_tweet, parse_metrics}, url::{parse_tweet_contents, timeline_item_to_tweet_item}};
+
+fn legacy() -> serde_json::Value {
+  json!({
+    "id_str": "1513868637307691009",
+    "full_text": "hello",
+    "created_at": "Tue Apr 12 13:16:24 +0000 2022",
+    "quoted_status_id_str": "1513000000000000000",
+    "in_reply_to_status_id_str": "1513800000000000000",
+    "conversation_id_str": "1513700000000000000",
+    "lang": "en",
+    "retweet_count": 3,
+    "reply_count": 2,
+    "quote_count": 1,
+    "favorite_count": 10,
+    "entities": {},
+  })
+}
+
+#[test]
+fn parsing_test_legacy_tweet() {
+  let tweet = parse_legacy_tweet(&legacy(), "epolynya", &json!({ "count": "42" }));
+  assert_eq!(tweet.id, "1513868637307691009");
+  assert_eq!(tweet.user, "epolynya");
+  assert_eq!(tweet.date_iso8601().as_deref(), Some("2022-04-12T13:16:24Z"));
+  assert_eq!(tweet.quoted_id.as_deref(), Some("1513000000000000000"));
+  assert_eq!(tweet.in_reply_to_status_id.as_deref(), Some("1513800000000000000"));
+  assert_eq!(tweet.conversation_id.as_deref(), Some("1513700000000000000"));
+  assert_eq!(tweet.lang.as_deref(), Some("en"));
+  let metrics = tweet.metrics.unwrap();
+  assert_eq!((metrics.retweets, metrics.faves, metrics.views), (3, 10, Some(42)));
+  assert!(tweet.quote.is_none() && tweet.retweeted_by.is_none());
+}
+
+#[test]
+fn parsing_test_metrics() {
+  println!("parse_metrics()  //  graphql string views, v2 number views, missing counts");
+  let graphql = json!({ "retweet_count": 3, "reply_count": 2, "quote_count": 1, "favorite_count": 10, "bookmark_count": 4 });
+  let metrics = parse_metrics(&graphql, &json!({ "count": "42", "state": "EnabledWithCount" }));
+  assert_eq!(metrics, TweetMetrics { retweets: 3, replies: 2, quotes: 1, faves: 10, bookmarks: Some(4), views: Some(42) });
+
+  let metrics = parse_metrics(&legacy(), &json!({ "count": 42 }));
+  assert_eq!((metrics.bookmarks, metrics.views), (None, Some(42)));
+
+  // old tweets have no view count, and missing counts are 0
+  let metrics = parse_metrics(&json!({ "favorite_count": 1 }), &json!({ "state": "Enabled" }));
+  assert_eq!(metrics, TweetMetrics { faves: 1, ..Default::default() });
+}
+
+/// graphql tweets (`url_to_tweets()`) and v2 tweets (`query_to_tweets()`) 
+/// must give the same fields
+#[test]
+fn parsing_test_graphql_matches_v2() {
+  let graphql = json!({
+    "__typename": "Tweet",
+    "core": { "user_results": { "result": { "legacy": { "screen_name": "epolynya" } } } },
+    "views": { "count": "42" },
+    "legacy": legacy(),
+  });
+  let from_graphql = parse_tweet_contents(&json!({ "result": graphql })).and_then(TweetItem::into_tweet).unwrap();
+  let from_v2 = parse_legacy_tweet(&legacy(), "epolynya", &json!({ "count": 42 }));
+  assert_eq!(format!("{from_graphql:?}"), format!("{from_v2:?}"));
+
+  let retweet = json!({
+    "__typename": "Tweet",
+    "core": { "user_results": { "result": { "legacy": { "screen_name": "retweeter" } } } },
+    "legacy": {
+      "id_str": "1", "full_text": "RT @epolynya: hello", "created_at": "Wed Apr 13 08:00:00 +0000 2022",
+      "retweeted_status_result": { "result": graphql },
+    },
+  });
+  let tweet = parse_tweet_contents(&json!({ "result": retweet })).and_then(TweetItem::into_tweet).unwrap();
+  assert_eq!(tweet.id, "1513868637307691009");
+  let retweet = &tweet.retweeted_by.unwrap()[0];
+  assert_eq!((retweet.user.as_str(), retweet.id.as_str()), ("retweeter", "1"));
+  assert_eq!(retweet.created_at.map(crate::date::format_iso8601).as_deref(), Some("2022-04-13T08:00:00Z"));
+}
+
+#[test]
+fn parsing_test_tweet_items() {
+  println!("timeline_item_to_tweet_item()  //  tombstone with id, show more cursor, user");
+  let timeline = Timeline::from_instructions(&json!([{ "type": "TimelineAddEntries", "entries": [{
+    "entryId": "conversationthread-1",
+    "content": { "entryType": "TimelineTimelineModule", "items": [
+      { "entryId": "conversationthread-1-tweet-2", "item": { "itemContent": {
+        "itemType": "TimelineTweet",
+        "tweet_results": { "result": {
+          "__typename": "TweetTombstone",
+          "tombstone": { "text": { "text": "This Tweet is from a suspended account. Learn more" } },
+        } },
+      } } },
+      { "entryId": "conversationthread-1-cursor-showmore-3", "item": { "itemContent": {
+        "itemType": "TimelineTimelineCursor", "value": "abc", "cursorType": "ShowMore",
+      } } },
+      { "entryId": "conversationthread-1-user-4", "item": { "itemContent": {
+        "itemType": "TimelineUser",
+      } } },
+    ] },
+  }] }]));
+  let items = match &timeline.entries[0].content {
+    EntryContent::Module(items) => items,
+    _ => panic!("not a module"),
+  };
+  let items: Vec<TweetItem> = items.iter()
+    .filter_map(|module_item| timeline_item_to_tweet_item(&module_item.item, &module_item.entry_id))
+    .collect();
+
+  assert!(matches!(&items[0], TweetItem::Tombstone { text, id: Some(id) }
+    if text == "This Tweet is from a suspended account." && id == "2"));
+  assert!(matches!(&items[1], TweetItem::Cursor { kind: CursorType::ShowMore, value } if value == "abc"));
+  assert!(matches!(&items[2], TweetItem::Unsupported { kind } if kind == "TimelineUser"));
+  assert!(items.iter().all(|item| item.as_tweet().is_none()));
+}
+
+#[test]
+fn parsing_test_deleted_quote() {
+  println!("parse_tweet_contents()  //  quote of a deleted tweet");
+  let mut quoting = legacy();
+  quoting["is_quote_status"] = json!(true);
+  let tweet = json!({ "result": {
+    "__typename": "Tweet",
+    "core": { "user_results": { "result": { "legacy": { "screen_name": "epolynya" } } } },
+    "quoted_status_result": { "result": {
+      "__typename": "TweetTombstone",
+      "tombstone": { "text": { "text": "This Tweet was deleted by the Tweet author. Learn more" } },
+    } },
+    "legacy": quoting,
+  } });
+  let tweet = parse_tweet_contents(&tweet).and_then(TweetItem::into_tweet).unwrap();
+  assert!(tweet.quote.is_none());
+  assert_eq!(tweet.quoted_id.as_deref(), Some("1513000000000000000"));
+}
+
+#[test]
+fn parsing_test_reply_metadata() {
+  println!("parse_legacy_tweet()  //  reply to two users");
+  let mut reply = legacy();
+  reply["full_text"] = json!("@epolynya @OngoingStudy agreed, @balajis too");
+  reply["in_reply_to_user_id_str"] = json!("123");
+  reply["in_reply_to_screen_name"] = json!("epolynya");
+  reply["display_text_range"] = json!([24, 44]);
+  reply["entities"] = json!({ "user_mentions": [
+    { "screen_name": "epolynya", "indices": [0, 9] },
+    { "screen_name": "OngoingStudy", "indices": [10, 23] },
+    { "screen_name": "balajis", "indices": [32, 40] },
+  ] });
+  let tweet = parse_legacy_tweet(&reply, "A", &json!({}));
+  assert!(tweet.is_reply());
+  assert_eq!(tweet.in_reply_to_user_id.as_deref(), Some("123"));
+  assert_eq!(tweet.in_reply_to_screen_name.as_deref(), Some("epolynya"));
+  assert_eq!(tweet.reply_mentions, Some(vec!["epolynya".to_string(), "OngoingStudy".to_string()]));
+
+  // without `display_text_range`, the leading mentions of the text are used
+  reply["display_text_range"] = json!(null);
+  let tweet = parse_legacy_tweet(&reply, "A", &json!({}));
+  assert_eq!(tweet.reply_mentions, Some(vec!["epolynya".to_string(), "OngoingStudy".to_string()]));
+
+  // not a reply
+  let mut not_reply = legacy();
+  not_reply["in_reply_to_status_id_str"] = json!(null);
+  not_reply["full_text"] = json!("@epolynya hi");
+  let tweet = parse_legacy_tweet(&not_reply, "A", &json!({}));
+  assert!(!tweet.is_reply());
+  assert_eq!(tweet.reply_mentions, None);
+}
+
+#[test]
+fn parsing_test_media() {
+  println!("parse_media()  //  photo, video with hls playlist, gif without bitrate");
+  let mut tweet = legacy();
+  tweet["extended_entities"] = json!({ "media": [
+    {
+      "id_str": "10", "type": "photo", "url": "https://t.co/m", "indices": [6, 20],
+      "media_url_https": "https://pbs.twimg.com/media/a.jpg",
+      "ext_alt_text": "a cat",
+      "original_info": { "width": 1200, "height": 800, "focus_rects": [{ "x": 0, "y": 10, "w": 1200, "h": 672 }] },
+      "sizes": { "thumb": { "w": 150, "h": 150, "resize": "crop" }, "large": { "w": 1200, "h": 800, "resize": "fit" } },
+    },
+    {
+      "id_str": "11", "type": "video", "url": "https://t.co/m",
+      "media_url_https": "https://pbs.twimg.com/ext_tw_video_thumb/11/pu/img/b.jpg",
+      "video_info": {
+        "aspect_ratio": [16, 9], "duration_millis": 30000,
+        "variants": [
+          { "bitrate": 832000, "content_type": "video/mp4", "url": "https://video.twimg.com/640x360.mp4" },
+          { "content_type": "application/x-mpegURL", "url": "https://video.twimg.com/pl.m3u8" },
+          { "bitrate": 2176000, "content_type": "video/mp4", "url": "https://video.twimg.com/1280x720.mp4" },
+        ],
+      },
+    },
+    {
+      "id_str": "12", "type": "animated_gif", "url": "https://t.co/m",
+      "media_url_https": "https://pbs.twimg.com/tweet_video_thumb/c.jpg",
+      "video_info": { "aspect_ratio": [1, 1], "variants": [
+        { "content_type": "video/mp4", "url": "https://video.twimg.com/tweet_video/c.mp4" },
+      ] },
+    },
+  ] });
+  let media = parse_legacy_tweet(&tweet, "A", &json!({})).media.unwrap();
+
+  let photo = &media[0];
+  assert!(matches!(photo.kind, MediaKind::Photo));
+  assert_eq!(photo.alt_text.as_deref(), Some("a cat"));
+  assert_eq!(photo.original_size, Some((1200, 800)));
+  assert_eq!(photo.crops, [MediaCrop { x: 0, y: 10, width: 1200, height: 672 }]);
+  assert_eq!(photo.sizes.iter().map(|size| size.name.as_str()).collect::<Vec<&str>>(), ["large", "thumb"]);
+  assert_eq!(photo.sized_img_url("orig"), "https://pbs.twimg.com/media/a.jpg?name=orig");
+  assert_eq!(photo.video_url(), None);
+
+  let video = media[1].video_info().unwrap();
+  assert!(matches!(media[1].kind, MediaKind::Video(_)));
+  assert_eq!((video.duration_ms, video.aspect_ratio), (Some(30000), Some((16, 9))));
+  assert_eq!(video.variants.len(), 3);
+  assert_eq!(media[1].video_url(), Some("https://video.twimg.com/1280x720.mp4"));
+  assert_eq!(video.hls_playlist().map(|v| v.url.as_str()), Some("https://video.twimg.com/pl.m3u8"));
+
+  assert!(matches!(media[2].kind, MediaKind::AnimatedGif(_)));
+  assert_eq!(media[2].video_url(), Some("https://video.twimg.com/tweet_video/c.mp4"));
+}
@@ -1,82 +1,82 @@
 use crate::{
-  fetch::query_fetch,
-  types::{Tweet, TweetExtra},
+  fetch::{query_fetch, query_page_fetch},
+  types::{Tweet, Retweet},
+  timeline::{Timeline, EntryContent, TimelineItem},
 };
-use super::parsing::{parse_urls, parse_media};
+use super::parsing::{parse_legacy_tweet, parse_author};
+use serde_json::Value;
 use std::collections::HashMap;
 
 /// get tweets from twitter search query
+/// 
+/// a tweet matched more than once (e.g. retweeted by several of the queried 
+/// users) is returned once, at its first position, with all its retweets in 
+/// `retweeted_by`
 pub async fn query_to_tweets(query: &str) -> Result<Vec<Tweet>, Box<dyn std::error::Error>> {
+  let fetch_json = query_fetch(query).await?;
+  Ok(parse_query_response(&fetch_json))
+}
+
+/// get a page of tweets from twitter search query, at a cursor (or "" for the
+/// first page), and the cursor of the next page
+///
+/// the cursor is `None` if there are no more pages
+pub async fn query_to_tweets_page(query: &str, cursor: &str
+) -> Result<(Vec<Tweet>, Option<String>), Box<dyn std::error::Error>> {
+  let fetch_json = query_page_fetch(query, cursor).await?;
+  Ok((parse_query_response(&fetch_json), parse_query_bottom_cursor(&fetch_json)))
+}
+
+/// the cursor of the next page of a search response
+pub(crate) fn parse_query_bottom_cursor(fetch_json: &Value) -> Option<String> {
+  Timeline::from_instructions(&fetch_json["timeline"]["instructions"]).bottom_cursor
+}
+
+/// parse the tweets of a search response, see `query_to_tweets()`
+pub(crate) fn parse_query_response(fetch_json: &Value) -> Vec<Tweet> {
   // key is the tweet item id, val is (tweet, quoted_tweet_id, retweeted_tweet_id)
   // quoted_tweet_id = the id of the tweet being quoted (to be able match quote 
   //   tweets to the quoted tweet)
   // retweeted_tweet_id = the id of the tweet being retweeted
   let mut parsed_tweets_map: HashMap<String, (Tweet, Option<String>, Option<String>)> = HashMap::new();
 
-  let fetch_json = query_fetch(query).await?;
-  
   // data is separated into users and tweets, so to attach username to tweet, 
   // need to get user info first
   
   /* -------------------------------- users -------------------------------- */
   let users_json = match fetch_json["globalObjects"]["users"].as_object() {
     Some(users) => users,
-    None => return Ok(Vec::new()),
+    None => return Vec::new(),
   };
-  let mut user_id_to_name_map: HashMap<&str, &str> = HashMap::new();
+  let mut user_id_to_json_map: HashMap<&str, &Value> = HashMap::new();
   for (_, user_json) in users_json {
     let id = user_json["id_str"].as_str().unwrap();
-    let name = user_json["screen_name"].as_str().unwrap();
-    user_id_to_name_map.insert(id, name);
+    user_id_to_json_map.insert(id, user_json);
   }
 
   let tweets_json = fetch_json["globalObjects"]["tweets"].as_object().unwrap();
 
   for (_, tweet_json) in tweets_json {
-    let id = tweet_json["id_str"].as_str().unwrap().to_string();
-    let user = user_id_to_name_map[tweet_json["user_id_str"].as_str().unwrap()].to_string();
-    let text = tweet_json["full_text"].as_str().unwrap().to_string();
-    let media = parse_media(tweet_json);
-    let urls = parse_urls(tweet_json);
-    let thread_id = tweet_json.get("self_thread")
-      .and_then(|o| Some(o.get("id_str").unwrap().as_str().unwrap().to_string()));
-    let date = tweet_json["created_at"].as_str().unwrap().to_string();
-    let quoted_tweet_id = tweet_json.get("quoted_status_id_str")
-      .and_then(|o| o.as_str()).and_then(|s| Some(s.to_string()));
+    let user_json = user_id_to_json_map[tweet_json["user_id_str"].as_str().unwrap()];
+    let user = user_json["screen_name"].as_str().unwrap();
+    let mut parsed_tweet = parse_legacy_tweet(tweet_json, user, &tweet_json["ext"]["views"]["r"]["ok"]);
+    parsed_tweet.author = parse_author(user_json);
+    let quoted_tweet_id = parsed_tweet.quoted_id.clone();
     let retweeted_tweet_id = tweet_json.get("retweeted_status_id_str")
-      .and_then(|o| o.as_str()).and_then(|s| Some(s.to_string()));
-    let faves = tweet_json["favorite_count"].as_u64().unwrap();
-
-    let parsed_tweet = Tweet {
-      id: id.clone(),
-      user,
-      text,
-      media,
-      urls,
-      quote: None,
-      thread_id,
-      extra: Some(TweetExtra {
-        date,
-        retweeted_by: None,
-        faves,
-      }),
-    };
-    parsed_tweets_map.insert(id, (parsed_tweet, quoted_tweet_id, retweeted_tweet_id));
+      .and_then(|o| o.as_str()).map(|s| s.to_string());
+    parsed_tweets_map.insert(parsed_tweet.id.clone(), (parsed_tweet, quoted_tweet_id, retweeted_tweet_id));
   }
 
   // these are all the ids of actual tweets, rather than e.g. quoted tweets.
   // note: the id for a retweet is the retweet item, rather than actual tweet
-  let timeline_tweet_ids = &fetch_json["timeline"]["instructions"][0]
-    ["addEntries"]["entries"].as_array().unwrap().into_iter()
-    .filter_map(|item| {
-      let id = item["entryId"].as_str().unwrap();
-      match id.starts_with("tweet-") {
-        true => Some((&id[6..]).to_string()),
-        false => None,
-      }
-    }).collect::<Vec<String>>();
+  let timeline = Timeline::from_instructions(&fetch_json["timeline"]["instructions"]);
+  let timeline_tweet_ids = timeline.entries.iter()
+    .filter_map(|entry| match &entry.content {
+      EntryContent::Item(TimelineItem::Tweet(tweet)) => tweet["id"].as_str(),
+      _ => None,
+    }).map(|id| id.to_string()).collect::<Vec<String>>();
 
-  let parsed_tweets: Vec<Tweet> = timeline_tweet_ids.into_iter()
+  let parsed_tweets = timeline_tweet_ids.iter()
     .map(|id| {
       let (mut tweet_item, mut quoted_tweet_id, retweeted_tweet_id,
       ) = parsed_tweets_map.get(id).unwrap().clone();
@@ -93,16 +93,15 @@ pub async fn query_to_tweets(query: &str) -> Result<Vec<Tweet>, Box<dyn std::err
       the retweet has the property retweeted_status_id_str, which is the id of 
       the retweeted tweet, and user_id_str, which is the id of the user that 
       retweeted
+
+      each retweet is its own item, so a tweet retweeted by two of the queried 
+      users shows up twice (once per retweet). these are merged below
       */
 
       // if this is a retweet item, return the retweeted tweet
       if let Some(retweeted_tweet_id) = retweeted_tweet_id {
-        // get who retweeted it
-        // FIXME: IF THIS GETS RETWEETED BY TWO PEOPLE, DOES IT FUCK UP BC THIS 
-        // IMPLEMENTATION DOESN'T ALLOW YOU TO ADD A USER IF THERE IS AN 
-        // EXISTING USER??? THEN AGAIN, DO RETWEET ITEMS GET COMBINED INTO 
-        // ONE??? HOW DO I GET BOTH USERS FROM THE RETWEET ITEM??
-        let retweeted_by = tweet_item.user.clone();
+        // get who retweeted it (and when)
+        let retweeted_by = Retweet::from_tweet(&tweet_item);
 
         // swap the tweet to the retweeted tweet, then add who it was retweeted 
         // by (we are changing the original tweet_item/quoted_tweet_id so we 
@@ -110,7 +109,7 @@ pub async fn query_to_tweets(query: &str) -> Result<Vec<Tweet>, Box<dyn std::err
         // non-retweeted tweet)
         (tweet_item, quoted_tweet_id, _,) = parsed_tweets_map
           .get(&retweeted_tweet_id).unwrap().clone();
-        tweet_item.extra.as_mut().unwrap().retweeted_by = Some(vec![retweeted_by]);
+        tweet_item.retweeted_by = Some(vec![retweeted_by]);
       }
 
       /*
@@ -120,15 +119,34 @@ pub async fn query_to_tweets(query: &str) -> Result<Vec<Tweet>, Box<dyn std::err
       */
 
       // if this tweet quotes a tweet, add the quoted tweet to it
+      // (the quoted tweet is missing if it can't be viewed, e.g. if deleted)
       if let Some(quoted_tweet_id) = quoted_tweet_id {
-        let (q_tweet_item, _, _,) = parsed_tweets_map
-          .get(&quoted_tweet_id).unwrap().clone();
-        tweet_item.quote = Some(Box::new(q_tweet_item.clone()));
+        tweet_item.quote = parsed_tweets_map.get(&quoted_tweet_id)
+          .map(|(q_tweet_item, _, _,)| Box::new(q_tweet_item.clone()));
       }
       tweet_item
-    }).collect();
+    });
 
-  Ok(parsed_tweets)
+  merge_duplicate_tweets(parsed_tweets)
+}
+
+/// merge tweets that appear more than once (e.g. a tweet retweeted by several 
+/// accounts) into their first occurrence, accumulating their retweets (see 
+/// `Tweet::merge()`)
+pub fn merge_duplicate_tweets(tweets: impl IntoIterator<Item = Tweet>) -> Vec<Tweet> {
+  let mut merged: Vec<Tweet> = Vec::new();
+  // tweet id to index in `merged`
+  let mut indices: HashMap<String, usize> = HashMap::new();
+  for tweet in tweets {
+    match indices.get(&tweet.id) {
+      Some(&i) => merged[i].merge(&tweet),
+      None => {
+        indices.insert(tweet.id.clone(), merged.len());
+        merged.push(tweet);
+      },
+    }
+  }
+  merged
 }
 
 /// extract the usernames from the search query
@@ -146,10 +164,7 @@ pub fn query_to_query_users(query: &str) -> Vec<String> {
   let mut detect_buf = String::from("     ");
   for char in query.chars() {
     if collecting_name {
-        let is_alphanumeric = match char {
-          'a'..='z' | 'A'..='Z' | '0'..='9' => true,
-          _ => false,
-        };
+        let is_alphanumeric = matches!(char, 'a'..='z' | 'A'..='Z' | '0'..='9');
         if is_alphanumeric {
           user_buf.push(char);
         } else {
@@ -161,7 +176,7 @@ pub fn query_to_query_users(query: &str) -> Vec<String> {
       // store last 5 chars as `detect_buf` to match "from:" when hit a ":"
       detect_buf.remove(0);
       detect_buf.push(char);
-      if char.to_string() == ":" && detect_buf == "from:" {
+      if char == ':' && detect_buf == "from:" {
         collecting_name = true;
       }
     }
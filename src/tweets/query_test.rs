@@ -1,4 +1,5 @@
-use super::query::{query_to_tweets, query_to_query_users};
+use serde_json::json;
+use super::query::{query_to_tweets, query_to_query_users, parse_query_response, parse_query_bottom_cursor};
 
 #[tokio::test]
 async fn query_test_1() {
@@ -86,6 +87,78 @@ fn query_test_parse() {
   assert!(vec!["elon".to_string(), "wooo".to_string(), "end".to_string()] == query_to_query_users(query));
 }
 
+#[test]
+fn query_test_merged_retweets() {
+  println!("parse_query_response()  //  tweet retweeted by two queried users shows up once");
+  let user = |id: &str, name: &str| json!({
+    "id_str": id, "screen_name": name, "name": name.to_uppercase(),
+    "profile_image_url_https": format!("https://pbs.twimg.com/profile_images/{id}/{name}_normal.jpg"),
+  });
+  let tweet = |id: &str, user_id: &str, created_at: &str, retweeted: Option<&str>| {
+    let mut tweet = json!({
+      "id_str": id, "user_id_str": user_id, "created_at": created_at,
+      "full_text": "hello", "entities": {},
+    });
+    if let Some(retweeted) = retweeted {
+      tweet["retweeted_status_id_str"] = json!(retweeted);
+      tweet["full_text"] = json!("RT @c: hello");
+    }
+    tweet
+  };
+  let entry = |id: &str, sort_index: &str| json!({
+    "entryId": format!("sq-I-t-{id}"), "sortIndex": sort_index,
+    "content": { "item": { "content": { "tweet": { "id": id, "displayType": "Tweet" } } } },
+  });
+  let response = json!({
+    "globalObjects": {
+      "users": { "1": user("1", "a"), "2": user("2", "b"), "3": user("3", "c") },
+      "tweets": {
+        "10": tweet("10", "3", "Mon Apr 11 10:00:00 +0000 2022", None),
+        "11": tweet("11", "3", "Mon Apr 11 11:00:00 +0000 2022", None),
+        "20": tweet("20", "1", "Tue Apr 12 10:00:00 +0000 2022", Some("10")),
+        "21": tweet("21", "2", "Tue Apr 12 09:00:00 +0000 2022", Some("10")),
+      },
+    },
+    "timeline": { "instructions": [{ "addEntries": { "entries": [
+      entry("20", "4"), entry("11", "3"), entry("21", "2"),
+    ] } }] },
+  });
+  let tweets = parse_query_response(&response);
+  assert_eq!(tweets.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec!["10", "11"]);
+  assert_eq!(tweets[0].user, "c");
+  let author = tweets[0].author.as_ref().unwrap();
+  assert_eq!(author.name, "C");
+  assert_eq!(author.avatar_url.as_deref(), Some("https://pbs.twimg.com/profile_images/3/c_normal.jpg"));
+  let retweets = tweets[0].retweeted_by.as_ref().unwrap();
+  assert_eq!(
+    retweets.iter().map(|r| (r.user.as_str(), r.id.as_str())).collect::<Vec<_>>(),
+    vec![("a", "20"), ("b", "21")],
+  );
+  assert!(retweets.iter().all(|r| r.created_at.is_some()));
+  assert!(tweets[1].retweeted_by.is_none());
+}
+
+#[test]
+fn query_test_bottom_cursor() {
+  println!("parse_query_bottom_cursor()  //  cursor of the next page, added or replaced");
+  let cursor = |value: &str, cursor_type: &str| json!({
+    "entryId": format!("sq-cursor-{}", cursor_type.to_lowercase()),
+    "content": { "operation": { "cursor": { "value": value, "cursorType": cursor_type } } },
+  });
+  // the first page adds the cursors
+  let response = json!({ "timeline": { "instructions": [{ "addEntries": { "entries": [
+    cursor("scroll:top", "Top"), cursor("scroll:bottom", "Bottom"),
+  ] } }] } });
+  assert_eq!(parse_query_bottom_cursor(&response).as_deref(), Some("scroll:bottom"));
+  // later pages replace them
+  let response = json!({ "timeline": { "instructions": [
+    { "addEntries": { "entries": [] } },
+    { "replaceEntry": { "entryIdToReplace": "sq-cursor-bottom", "entry": cursor("scroll:next", "Bottom") } },
+  ] } });
+  assert_eq!(parse_query_bottom_cursor(&response).as_deref(), Some("scroll:next"));
+  assert_eq!(parse_query_bottom_cursor(&json!({})), None);
+}
+
 // todo: delete after confirmed tinyfeed is updating
 // #[tokio::test]
 // async fn query_test_111() {
@@ -0,0 +1,37 @@
+//! the profile-level tweet stats of a user (`TweetStats`)
+//!
+//! for the engagement counts of a single tweet (retweets, replies, likes, 
+//! views, etc.), use `tweets::url::url_to_metrics()`
+
+use std::{collections::BTreeMap, error::Error};
+use serde_json::Value;
+use crate::{fetch::tweet_stats_fetch, types::UserTweetStats};
+
+/// get the tweet stats of a user, by their numeric user id
+///
+/// returns an error if the response has no tweet stats (e.g. the user doesn't
+/// exist, or twitter has turned the stats off)
+pub async fn user_tweet_stats(user_id: &str) -> Result<UserTweetStats, Box<dyn Error>> {
+  let data = tweet_stats_fetch(user_id).await?;
+  parse_tweet_stats(user_id, &data).ok_or_else(|| "no tweet stats in the response".into())
+}
+
+/// parse the `data` of a `TweetStats` response, which nests the stats as 
+/// `user_result_by_rest_id.result.tweet_stats` (or `user.result.tweet_stats`)
+///
+/// numbers can be json numbers or strings (like the view counts of tweets),
+/// other values are skipped
+pub fn parse_tweet_stats(user_id: &str, data: &Value) -> Option<UserTweetStats> {
+  let user = data.get("user_result_by_rest_id").or_else(|| data.get("user"))?.get("result")?;
+  let stats = user.get("tweet_stats")?.as_object()?.iter()
+    .filter_map(|(name, value)| {
+      let value = value.as_f64().or_else(|| value.as_str()?.parse().ok())?;
+      Some((name.clone(), value))
+    })
+    .collect::<BTreeMap<String, f64>>();
+  Some(UserTweetStats {
+    user_id: user["rest_id"].as_str().unwrap_or(user_id).to_string(),
+    tweet_frequency: stats.get("tweet_frequency").copied(),
+    stats,
+  })
+}
@@ -0,0 +1,26 @@
+use serde_json::json;
+use super::stats::parse_tweet_stats;
+
+#[test]
+fn stats_test_parse() {
+  println!("parse_tweet_stats()  //  numbers and numeric strings, other values skipped");
+  let data = json!({ "user_result_by_rest_id": { "result": {
+    "__typename": "User",
+    "rest_id": "44196397",
+    "tweet_stats": { "tweet_frequency": 12.5, "tweet_count_last_week": "87", "period": "week" },
+  } } });
+  let stats = parse_tweet_stats("44196397", &data).unwrap();
+  assert_eq!(stats.user_id, "44196397");
+  assert_eq!(stats.tweet_frequency, Some(12.5));
+  assert_eq!(stats.stats.get("tweet_count_last_week"), Some(&87.0));
+  assert_eq!(stats.stats.len(), 2);
+
+  // `user.result` nesting, and no `tweet_frequency`
+  let data = json!({ "user": { "result": { "tweet_stats": { "tweet_count_last_week": 3 } } } });
+  let stats = parse_tweet_stats("1", &data).unwrap();
+  assert_eq!((stats.user_id.as_str(), stats.tweet_frequency), ("1", None));
+
+  // e.g. the user doesn't exist
+  assert_eq!(parse_tweet_stats("1", &json!({ "user_result_by_rest_id": {} })), None);
+  assert_eq!(parse_tweet_stats("1", &json!(null)), None);
+}
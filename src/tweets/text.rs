@@ -0,0 +1,255 @@
+//! working with the text of tweets
+//!
+//! `full_text` is html-escaped ("&" is "&amp;", etc.), while the indices of
+//! entities count the unescaped characters, so the text must be decoded before
+//! the indices are used
+
+use crate::{types::{Tweet, TextRange}, tweets::tweet_ref::TweetRef};
+
+/// output format of `render_text()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+  /// plain text, urls are expanded but nothing is linked
+  Plain,
+  /// markdown, with urls, mentions, hashtags and cashtags as links
+  Markdown,
+  /// html, with urls, mentions, hashtags and cashtags as `<a>` links
+  Html,
+}
+
+/// options for `render_text()`. the default is plain text with everything 
+/// twitter hides stripped
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+  pub format: TextFormat,
+  /// show urls as their shortened display form (e.g. "example.com/a-long-pa…")
+  /// rather than the full expanded url. for markdown/html this is the link text
+  pub display_urls: bool,
+  /// strip the "@user" mentions at the start of replies
+  pub strip_reply_mentions: bool,
+  /// strip the trailing links to the tweet's media
+  pub strip_media_links: bool,
+  /// strip the trailing link to the quoted tweet
+  pub strip_quote_link: bool,
+}
+
+impl Default for RenderOptions {
+  fn default() -> RenderOptions {
+    RenderOptions {
+      format: TextFormat::Plain,
+      display_urls: false,
+      strip_reply_mentions: true,
+      strip_media_links: true,
+      strip_quote_link: true,
+    }
+  }
+}
+
+impl RenderOptions {
+  /// the default options with the given format
+  pub fn new(format: TextFormat) -> RenderOptions {
+    RenderOptions { format, ..Default::default() }
+  }
+}
+
+/// a part of the text to replace
+struct Span {
+  range: TextRange,
+  /// link text and url
+  link: (String, String),
+  /// whether the span is stripped if it's at the end of the text (media and 
+  /// quote tweet links)
+  strip_if_trailing: bool,
+}
+
+/// get the display-ready text of a tweet: the `t.co` links replaced with the 
+/// urls they link to, the trailing media/quote tweet links and leading reply 
+/// mentions stripped, and html entities decoded
+///
+/// e.g. "@user check this out &amp; https://t.co/abc https://t.co/media" ->
+/// "check this out & https://example.com/abc"
+pub fn render_text(tweet: &Tweet, options: &RenderOptions) -> String {
+  let text = decode_html_entities(&tweet.text);
+  let chars: Vec<char> = text.chars().collect();
+  let mut spans: Vec<Span> = Vec::new();
+
+  for url in tweet.urls.iter().flatten() {
+    let range = match url.range.or_else(|| find_range(&text, &url.shortened_url)) {
+      Some(range) => range,
+      None => continue,
+    };
+    let link_text = match options.display_urls && !url.display_url.is_empty() {
+      true => url.display_url.clone(),
+      false => url.full_url.clone(),
+    };
+    let is_quote_link = options.strip_quote_link && tweet.quoted_id.is_some()
+      && TweetRef::parse(&url.full_url).ok().map(|r| r.id) == tweet.quoted_id;
+    spans.push(Span { range, link: (link_text, url.full_url.clone()), strip_if_trailing: is_quote_link });
+  }
+  for media in tweet.media.iter().flatten() {
+    if let Some(range) = media.range.or_else(|| find_range(&text, &media.shortened_img_url)) {
+      let link = (media.shortened_img_url.clone(), media.shortened_img_url.clone());
+      spans.push(Span { range, link, strip_if_trailing: options.strip_media_links });
+    }
+  }
+  if options.format != TextFormat::Plain {
+    let entities = &tweet.entities;
+    for mention in &entities.mentions {
+      let link = (format!("@{}", mention.screen_name), format!("https://twitter.com/{}", mention.screen_name));
+      spans.push(Span { range: mention.range, link, strip_if_trailing: false });
+    }
+    for hashtag in &entities.hashtags {
+      let link = (format!("#{}", hashtag.text), format!("https://twitter.com/hashtag/{}", hashtag.text));
+      spans.push(Span { range: hashtag.range, link, strip_if_trailing: false });
+    }
+    for cashtag in &entities.cashtags {
+      let link = (format!("${}", cashtag.text), format!("https://twitter.com/search?q=%24{}", cashtag.text));
+      spans.push(Span { range: cashtag.range, link, strip_if_trailing: false });
+    }
+  }
+  // rm spans past the end of the text, and overlapping spans
+  spans.retain(|span| span.range.end <= chars.len());
+  spans.sort_by_key(|span| (span.range.start, span.range.end));
+  let mut last_end = 0;
+  spans.retain(|span| {
+    let keep = span.range.start >= last_end;
+    if keep {
+      last_end = span.range.end;
+    }
+    keep
+  });
+
+  // the reply mentions are everything before `display_text_range`
+  let start = match (options.strip_reply_mentions, tweet.reply_mentions.is_some(), tweet.display_text_range) {
+    (true, true, Some(display_range)) if display_range.start <= chars.len() => display_range.start,
+    _ => 0,
+  };
+  // strip the trailing media/quote tweet links (and the whitespace between them)
+  let mut end = chars.len();
+  for span in spans.iter().rev() {
+    let is_trailing = chars[span.range.end..end].iter().all(|c| c.is_whitespace());
+    if !is_trailing || !span.strip_if_trailing {
+      break;
+    }
+    end = span.range.start;
+  }
+
+  let mut rendered = String::new();
+  let mut pos = start;
+  for span in spans.iter().filter(|span| span.range.start >= start && span.range.end <= end) {
+    push_text(&mut rendered, &chars[pos..span.range.start], options.format);
+    let (link_text, url) = &span.link;
+    match options.format {
+      TextFormat::Plain => rendered.push_str(link_text),
+      TextFormat::Markdown => {
+        rendered.push('[');
+        push_text(&mut rendered, &link_text.chars().collect::<Vec<char>>(), TextFormat::Markdown);
+        rendered.push_str(&format!("]({})", url.replace('(', "%28").replace(')', "%29")));
+      },
+      TextFormat::Html => {
+        rendered.push_str(&format!("<a href=\"{}\">", escape_html(url)));
+        rendered.push_str(&escape_html(link_text));
+        rendered.push_str("</a>");
+      },
+    }
+    pos = span.range.end;
+  }
+  push_text(&mut rendered, &chars[pos.min(end)..end], options.format);
+  rendered.trim().to_string()
+}
+
+impl Tweet {
+  /// the display-ready text of the tweet, see `render_text()`
+  pub fn render_text(&self, format: TextFormat) -> String {
+    render_text(self, &RenderOptions::new(format))
+  }
+}
+
+/// find the first occurrence of `needle` in `text` as a `TextRange`
+fn find_range(text: &str, needle: &str) -> Option<TextRange> {
+  if needle.is_empty() {
+    return None;
+  }
+  let byte_start = text.find(needle)?;
+  let start = text[..byte_start].chars().count();
+  Some(TextRange::new(start, start + needle.chars().count()))
+}
+
+/// push plain text, escaped for the format
+fn push_text(rendered: &mut String, text: &[char], format: TextFormat) {
+  for &c in text {
+    match format {
+      TextFormat::Plain => rendered.push(c),
+      TextFormat::Markdown => {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+          rendered.push('\\');
+        }
+        rendered.push(c);
+      },
+      TextFormat::Html => match c {
+        '&' => rendered.push_str("&amp;"),
+        '<' => rendered.push_str("&lt;"),
+        '>' => rendered.push_str("&gt;"),
+        '"' => rendered.push_str("&quot;"),
+        // keep line breaks of multi-line tweets
+        '\n' => rendered.push_str("<br>\n"),
+        c => rendered.push(c),
+      },
+    }
+  }
+}
+
+fn escape_html(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  push_text(&mut escaped, &text.chars().filter(|c| *c != '\n').collect::<Vec<char>>(), TextFormat::Html);
+  escaped
+}
+
+/// decode the html entities in tweet text, e.g. "a &amp; b" -> "a & b"
+///
+/// twitter only escapes "&", "<" and ">", but numeric entities (e.g. "&#39;")
+/// and other common named entities are decoded too. unknown entities are left
+/// as is
+pub fn decode_html_entities(text: &str) -> String {
+  let mut decoded = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(amp) = rest.find('&') {
+    decoded.push_str(&rest[..amp]);
+    rest = &rest[amp..];
+    // entities are short, so don't search the whole text for the ";"
+    let entity = rest.char_indices().take(12).find(|(_, c)| *c == ';')
+      .and_then(|(semi, _)| decode_entity(&rest[1..semi]).map(|c| (c, semi)));
+    match entity {
+      Some((c, semi)) => {
+        decoded.push(c);
+        rest = &rest[(semi + 1)..];
+      },
+      None => {
+        decoded.push('&');
+        rest = &rest[1..];
+      },
+    }
+  }
+  decoded.push_str(rest);
+  decoded
+}
+
+/// decode an entity without the "&" and ";", e.g. "amp" -> '&'
+fn decode_entity(entity: &str) -> Option<char> {
+  let code = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+    u32::from_str_radix(hex, 16).ok()?
+  } else if let Some(dec) = entity.strip_prefix('#') {
+    dec.parse().ok()?
+  } else {
+    return match entity {
+      "amp" => Some('&'),
+      "lt" => Some('<'),
+      "gt" => Some('>'),
+      "quot" => Some('"'),
+      "apos" => Some('\''),
+      "nbsp" => Some('\u{a0}'),
+      _ => None,
+    };
+  };
+  char::from_u32(code)
+}
@@ -0,0 +1,126 @@
+use serde_json::json;
+use crate::types::TextRange;
+use super::{parsing::parse_legacy_tweet, text::{decode_html_entities, render_text, RenderOptions, TextFormat}};
+
+#[test]
+fn text_test_decode_html_entities() {
+  assert_eq!(decode_html_entities("a &amp; b &lt;3 &gt;_&gt;"), "a & b <3 >_>");
+  assert_eq!(decode_html_entities("it&#39;s &#x1F389; &quot;hi&quot;"), "it's 🎉 \"hi\"");
+  // not entities
+  assert_eq!(decode_html_entities("AT&T & co &nope; &amp"), "AT&T & co &nope; &amp");
+  assert_eq!(decode_html_entities("&&amp;"), "&&");
+}
+
+#[test]
+fn text_test_ranges() {
+  let text = "🎉 #rust 日本";
+  let hashtag = TextRange::new(2, 7);
+  assert_eq!(hashtag.slice(text), Some("#rust"));
+  assert_eq!(hashtag.byte_range(text), Some(5..10));
+  // the emoji is 2 utf-16 code units
+  assert_eq!(hashtag.utf16_range(text), Some(3..8));
+  assert_eq!(TextRange::from_utf16_range(text, 3..8), Some(hashtag));
+  // to the end of the text
+  let end = TextRange::new(8, 10);
+  assert_eq!(end.slice(text), Some("日本"));
+  assert_eq!(end.utf16_range(text), Some(9..11));
+  assert_eq!(TextRange::from_utf16_range(text, 9..11), Some(end));
+  // out of bounds / inside a surrogate pair
+  assert_eq!(TextRange::new(8, 11).slice(text), None);
+  assert_eq!(TextRange::from_utf16_range(text, 1..8), None);
+  assert_eq!(TextRange::from_indices(&json!([3, 1])), None);
+}
+
+#[test]
+fn text_test_entities() {
+  println!("parse_legacy_tweet()  //  entities after an emoji and an html entity");
+  let tweet = parse_legacy_tweet(&json!({
+    "id_str": "1",
+    "full_text": "@epolynya 🎉 &amp; #rust $TWTR @balajis https://t.co/abc",
+    "display_text_range": [10, 34],
+    "entities": {
+      "hashtags": [{ "text": "rust", "indices": [14, 19] }],
+      "user_mentions": [
+        { "screen_name": "epolynya", "name": "Polynya", "id_str": "2", "indices": [0, 9] },
+        { "screen_name": "balajis", "indices": [26, 34] },
+      ],
+      "symbols": [{ "text": "TWTR", "indices": [20, 25] }],
+      "urls": [{
+        "url": "https://t.co/abc", "expanded_url": "https://example.com/a",
+        "display_url": "example.com/a", "indices": [35, 51],
+      }],
+    },
+  }), "A", &json!({}));
+
+  let text = decode_html_entities(&tweet.text);
+  let entities = &tweet.entities;
+  assert_eq!(entities.hashtags[0].text, "rust");
+  assert_eq!(entities.hashtags[0].range.slice(&text), Some("#rust"));
+  assert_eq!(entities.cashtags[0].range.slice(&text), Some("$TWTR"));
+  assert_eq!(entities.mentions[0].range.slice(&text), Some("@epolynya"));
+  assert_eq!(entities.mentions[0].name.as_deref(), Some("Polynya"));
+  assert_eq!(entities.mentions[1].range.slice(&text), Some("@balajis"));
+  let url = &tweet.urls.as_ref().unwrap()[0];
+  assert_eq!(url.display_url, "example.com/a");
+  assert_eq!(url.range.unwrap().slice(&text), Some("https://t.co/abc"));
+  assert_eq!(tweet.display_text(), "🎉 & #rust $TWTR @balajis");
+}
+
+fn reply_tweet() -> crate::types::Tweet {
+  parse_legacy_tweet(&json!({
+    "id_str": "1",
+    "full_text": "@epolynya look &amp; see #rust https://t.co/abc https://t.co/quote https://t.co/media",
+    "display_text_range": [10, 62],
+    "in_reply_to_status_id_str": "2",
+    "quoted_status_id_str": "3",
+    "entities": {
+      "hashtags": [{ "text": "rust", "indices": [21, 26] }],
+      "user_mentions": [{ "screen_name": "epolynya", "indices": [0, 9] }],
+      "urls": [
+        { "url": "https://t.co/abc", "expanded_url": "https://example.com/a_(b)",
+          "display_url": "example.com/a_(b)", "indices": [27, 43] },
+        { "url": "https://t.co/quote", "expanded_url": "https://twitter.com/balajis/status/3",
+          "display_url": "twitter.com/balajis/statu…", "indices": [44, 62] },
+      ],
+    },
+    "extended_entities": { "media": [{
+      "url": "https://t.co/media", "media_url_https": "https://pbs.twimg.com/media/a.jpg", "type": "photo",
+    }] },
+  }), "A", &json!({}))
+}
+
+#[test]
+fn text_test_render_plain() {
+  let tweet = reply_tweet();
+  assert_eq!(render_text(&tweet, &RenderOptions::default()),
+    "look & see #rust https://example.com/a_(b)");
+  assert_eq!(render_text(&tweet, &RenderOptions { display_urls: true, ..Default::default() }),
+    "look & see #rust example.com/a_(b)");
+  // nothing stripped
+  let options = RenderOptions {
+    strip_reply_mentions: false,
+    strip_media_links: false,
+    strip_quote_link: false,
+    ..Default::default()
+  };
+  assert_eq!(render_text(&tweet, &options),
+    "@epolynya look & see #rust https://example.com/a_(b) https://twitter.com/balajis/status/3 https://t.co/media");
+  // quote link is only stripped if it's trailing
+  let options = RenderOptions { strip_media_links: false, ..Default::default() };
+  assert_eq!(render_text(&tweet, &options),
+    "look & see #rust https://example.com/a_(b) https://twitter.com/balajis/status/3 https://t.co/media");
+}
+
+#[test]
+fn text_test_render_markdown_html() {
+  let tweet = reply_tweet();
+  assert_eq!(tweet.render_text(TextFormat::Markdown),
+    "look & see [\\#rust](https://twitter.com/hashtag/rust) \
+    [https://example.com/a\\_(b)](https://example.com/a_%28b%29)");
+  assert_eq!(tweet.render_text(TextFormat::Html),
+    "look &amp; see <a href=\"https://twitter.com/hashtag/rust\">#rust</a> \
+    <a href=\"https://example.com/a_(b)\">https://example.com/a_(b)</a>");
+  let options = RenderOptions { strip_reply_mentions: false, ..RenderOptions::new(TextFormat::Html) };
+  assert!(render_text(&tweet, &options)
+    .starts_with("<a href=\"https://twitter.com/epolynya\">@epolynya</a> look"));
+}
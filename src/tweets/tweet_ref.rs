@@ -0,0 +1,108 @@
+use std::{error::Error, fmt, str::FromStr};
+
+/// a reference to a tweet, parsed from a tweet url or a bare tweet id
+///
+/// accepted forms:
+/// - `1513868637307691009`
+/// - `https://twitter.com/epolynya/status/1513868637307691009?s=20`
+/// - `twitter.com/epolynya/status/1513868637307691009/photo/1`
+/// - `https://mobile.twitter.com/...`, `https://x.com/...`, `https://www.x.com/...`
+/// - `https://twitter.com/i/web/status/1513868637307691009`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TweetRef {
+  /// id number of tweet (validated to be a number)
+  pub id: String,
+  /// username in the url, if present (`/i/web/status/<id>` links and bare ids
+  /// don't have one)
+  pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TweetRefError {
+  /// the input was empty
+  Empty,
+  /// the url is not a twitter.com/x.com url
+  UnsupportedHost(String),
+  /// the url is a twitter url, but not a link to a tweet
+  NotATweetUrl(String),
+  /// the tweet id is not a valid number
+  InvalidId(String),
+}
+
+impl fmt::Display for TweetRefError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TweetRefError::Empty => write!(f, "empty tweet url/id"),
+      TweetRefError::UnsupportedHost(host) => write!(f, "not a twitter url (host: {host})"),
+      TweetRefError::NotATweetUrl(url) => write!(f, "not a link to a tweet: {url}"),
+      TweetRefError::InvalidId(id) => write!(f, "invalid tweet id: {id}"),
+    }
+  }
+}
+
+impl Error for TweetRefError {}
+
+impl TweetRef {
+  /// parse a tweet url or bare tweet id
+  pub fn parse(input: &str) -> Result<TweetRef, TweetRefError> {
+    let input = input.trim();
+    if input.is_empty() {
+      return Err(TweetRefError::Empty);
+    }
+    // bare tweet id
+    if input.bytes().all(|b| b.is_ascii_digit()) {
+      return Ok(TweetRef { id: validate_id(input)?, user: None });
+    }
+
+    // rm scheme, then query string and fragment (e.g. "?s=20")
+    let url = input.strip_prefix("https://")
+      .or_else(|| input.strip_prefix("http://"))
+      .unwrap_or(input);
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+
+    let (host, path) = url.split_once('/').unwrap_or((url, ""));
+    let host = host.to_lowercase();
+    let domain = host.strip_prefix("www.")
+      .or_else(|| host.strip_prefix("mobile."))
+      .unwrap_or(&host);
+    if domain != "twitter.com" && domain != "x.com" {
+      return Err(TweetRefError::UnsupportedHost(host));
+    }
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+      // twitter.com/i/web/status/<id>, twitter.com/i/status/<id>
+      ["i", "web", "status", id, ..] | ["i", "status", id, ..] => {
+        Ok(TweetRef { id: validate_id(id)?, user: None })
+      },
+      // twitter.com/<user>/status/<id>, optionally followed by e.g. /photo/1
+      [user, "status" | "statuses", id, ..] if is_valid_username(user) => {
+        Ok(TweetRef { id: validate_id(id)?, user: Some(user.to_string()) })
+      },
+      _ => Err(TweetRefError::NotATweetUrl(input.to_string())),
+    }
+  }
+}
+
+impl FromStr for TweetRef {
+  type Err = TweetRefError;
+
+  fn from_str(input: &str) -> Result<TweetRef, TweetRefError> {
+    TweetRef::parse(input)
+  }
+}
+
+/// tweet ids are snowflakes, so must be a positive number that fits in a u64
+fn validate_id(id: &str) -> Result<String, TweetRefError> {
+  match id.parse::<u64>() {
+    Ok(n) if n > 0 && id.bytes().all(|b| b.is_ascii_digit()) => Ok(n.to_string()),
+    _ => Err(TweetRefError::InvalidId(id.to_string())),
+  }
+}
+
+/// allowed chars in twitter name are same as `\w`, and max 15 chars
+fn is_valid_username(user: &str) -> bool {
+  !user.is_empty() && user.len() <= 15
+    && user.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,48 @@
+use super::tweet_ref::{TweetRef, TweetRefError};
+
+fn tweet_ref(id: &str, user: Option<&str>) -> TweetRef {
+  TweetRef { id: id.to_string(), user: user.map(|u| u.to_string()) }
+}
+
+#[test]
+fn tweet_ref_test_valid() {
+  let epolynya = tweet_ref("1513868637307691009", Some("epolynya"));
+  for url in [
+    "https://twitter.com/epolynya/status/1513868637307691009",
+    "https://twitter.com/epolynya/status/1513868637307691009?s=20&t=abc",
+    "https://twitter.com/epolynya/status/1513868637307691009/",
+    "https://twitter.com/epolynya/status/1513868637307691009#m",
+    "http://www.twitter.com/epolynya/status/1513868637307691009",
+    "https://mobile.twitter.com/epolynya/status/1513868637307691009",
+    "twitter.com/epolynya/status/1513868637307691009",
+    "https://x.com/epolynya/status/1513868637307691009",
+    "https://www.x.com/epolynya/status/1513868637307691009",
+    "https://twitter.com/epolynya/status/1513868637307691009/photo/1",
+    "https://twitter.com/epolynya/statuses/1513868637307691009",
+    "  https://TWITTER.com/epolynya/status/1513868637307691009  ",
+  ] {
+    assert_eq!(TweetRef::parse(url), Ok(epolynya.clone()), "{url}");
+  }
+
+  let no_user = tweet_ref("1513868637307691009", None);
+  assert_eq!(TweetRef::parse("https://twitter.com/i/web/status/1513868637307691009"), Ok(no_user.clone()));
+  assert_eq!(TweetRef::parse("https://x.com/i/status/1513868637307691009"), Ok(no_user.clone()));
+  assert_eq!("1513868637307691009".parse::<TweetRef>(), Ok(no_user));
+}
+
+#[test]
+fn tweet_ref_test_invalid() {
+  assert_eq!(TweetRef::parse(""), Err(TweetRefError::Empty));
+  assert_eq!(TweetRef::parse("https://t.co/abc"),
+    Err(TweetRefError::UnsupportedHost("t.co".to_string())));
+  assert_eq!(TweetRef::parse("https://nottwitter.com/a/status/1"),
+    Err(TweetRefError::UnsupportedHost("nottwitter.com".to_string())));
+  assert!(matches!(TweetRef::parse("https://twitter.com/epolynya"), Err(TweetRefError::NotATweetUrl(_))));
+  assert!(matches!(TweetRef::parse("https://twitter.com/epolynya/status"), Err(TweetRefError::NotATweetUrl(_))));
+  assert!(matches!(TweetRef::parse("https://twitter.com/i/lists/123"), Err(TweetRefError::NotATweetUrl(_))));
+  assert_eq!(TweetRef::parse("https://twitter.com/epolynya/status/12ab"),
+    Err(TweetRefError::InvalidId("12ab".to_string())));
+  assert_eq!(TweetRef::parse("99999999999999999999999"),
+    Err(TweetRefError::InvalidId("99999999999999999999999".to_string())));
+  assert_eq!(TweetRef::parse("0"), Err(TweetRefError::InvalidId("0".to_string())));
+}
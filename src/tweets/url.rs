@@ -1,51 +1,82 @@
-use std::{collections::HashSet, error::Error};
+use std::{collections::HashSet, error::Error, fmt};
 use serde_json::Value;
 use tokio::time::{sleep, Duration};
 use crate::{
   fetch::id_fetch,
-  types::Tweet,
-  tweets::parsing::{parse_urls, parse_media},
+  types::{Tweet, TweetItem, TweetMetrics, Retweet},
+  date::parse_twitter_date,
+  tweets::{parsing::{parse_legacy_tweet, parse_author}, cards::parse_card, tweet_ref::TweetRef},
+  timeline::{Timeline, TimelineEntry, EntryContent, ModuleItem, TimelineItem},
 };
 
+/// the tweet isn't in the response (e.g. it was deleted, or its account is 
+/// suspended or protected), with the id of the tweet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TweetNotFound(pub String);
+
+impl fmt::Display for TweetNotFound {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "tweet not found or unavailable: {}", self.0)
+  }
+}
+
+impl Error for TweetNotFound {}
+
+/// get the tweet/tweet-thread at a tweet url (or bare tweet id)
+///
+/// tweets in the thread that can't be viewed are left out, use 
+/// `url_to_tweet_items()` to get them as tombstones
+///
+/// returns a `TweetRefError` if the url isn't a link to a tweet, or a 
+/// `TweetNotFound` if the tweet isn't in the response
 pub async fn url_to_tweets(url: &str) -> Result<Vec<Tweet>, Box<dyn Error>> {
-  let tweet_id = url.split("/").collect::<Vec<&str>>()[5];
-  let mut tweets = url_to_tweets_no_cursor_position(tweet_id).await?;
-
-  // if tweet thread has not finished, change cursor position to get next 
-  // tweets. loop until have all tweets
-  // if the tweet_item is a "show more" button, i added it as a tweet where 
-  // the `id` is "more_tweets_in_thread", and the `text` is the "show more" 
-  // cursor position
-  let mut last_tweet = &tweets[tweets.len() -1];
-  while &last_tweet.id == "more_tweets_in_thread" {
-    let cursor = &last_tweet.text.clone();
-    // rm the "show more" temp tweet (last tweet) bc don't need anymore
-    tweets.pop();
+  Ok(url_to_tweet_items(url).await?.into_iter()
+    .filter_map(TweetItem::into_tweet)
+    .collect())
+}
+
+/// same as `url_to_tweets()`, but tweets that can't be viewed are returned as 
+/// `TweetItem::Tombstone`
+pub async fn url_to_tweet_items(url: &str) -> Result<Vec<TweetItem>, Box<dyn Error>> {
+  let tweet_ref = TweetRef::parse(url)?;
+  let tweet_id = tweet_ref.id.as_str();
+  let mut items = url_to_tweets_no_cursor_position(tweet_id).await?;
+
+  // if tweet thread has not finished, the last item is a "show more" cursor, 
+  // so change cursor position to get next tweets. loop until have all tweets
+  let mut seen_cursors: HashSet<String> = HashSet::new();
+  while let Some(TweetItem::Cursor { value: cursor, .. }) = items.last() {
+    let cursor = cursor.clone();
+    // rm the "show more" cursor bc don't need anymore
+    items.pop();
+    // stop if the server hands back a cursor already followed
+    if !seen_cursors.insert(cursor.clone()) {
+      break;
+    }
 
     // get extra tweets past "show more"
     sleep(Duration::from_millis(200)).await; // wait between requests
-    let show_more_tweets = url_to_tweets_with_cursor_position(tweet_id, cursor.as_str()).await?;
+    let show_more_items = url_to_tweets_with_cursor_position(tweet_id, cursor.as_str()).await?;
     
     // add tweets, checking to make sure they are unique
-    let existing_tweet_ids = tweets.iter()
-      .map(|t| t.id.clone()).collect::<HashSet<String>>();
-    for show_more_tweet in show_more_tweets {
-      // if tweet is not already in `tweets`, add it
-      if ! existing_tweet_ids.contains(&show_more_tweet.id) {
-        tweets.push(show_more_tweet);
+    let existing_tweet_ids = items.iter()
+      .filter_map(|item| item.id().map(|id| id.to_string()))
+      .collect::<HashSet<String>>();
+    for show_more_item in show_more_items {
+      // if tweet is not already in `items`, add it
+      if !show_more_item.id().map_or(false, |id| existing_tweet_ids.contains(id)) {
+        items.push(show_more_item);
       }
     }
-    // get last tweet so while loop can check if it is a "show_more"
-    last_tweet = &tweets[tweets.len() -1];
   }
-  Ok(tweets)
+  Ok(items)
 }
 
 async fn url_to_tweets_with_cursor_position(tweet_id: &str, cursor: &str
-) -> Result<Vec<Tweet>, Box<dyn Error>> {
-  let tweet_groups_json = id_fetch(tweet_id, cursor, false).await?;
-  let tweet_group = tweet_groups_json.as_array().unwrap();
-  Ok(tweet_group_to_tweets(&tweet_group))
+) -> Result<Vec<TweetItem>, Box<dyn Error>> {
+  // the "show more" response adds the items to the module of the thread
+  let timeline = Timeline::from_instructions(&id_fetch(tweet_id, cursor, false).await?);
+  Ok(timeline.entries.iter().flat_map(tweet_group_to_tweet_or_tweets).collect())
 }
 
 /// get a tweet/tweet-thread in a parsed format (most of the junk removed), as a
@@ -54,15 +85,22 @@ async fn url_to_tweets_with_cursor_position(tweet_id: &str, cursor: &str
 /// if more information is required than in the struct `Tweet`, use id_fetch()` 
 /// instead
 async fn url_to_tweets_no_cursor_position(tweet_id: &str
-) -> Result<Vec<Tweet>, Box<dyn Error>> {
-  let tweet_groups_json = id_fetch(
-    tweet_id, "", false).await?;
-  let tweet_groups = tweet_groups_json.as_array().unwrap();
+) -> Result<Vec<TweetItem>, Box<dyn Error>> {
+  let timeline = Timeline::from_instructions(&id_fetch(tweet_id, "", false).await?);
+  Ok(main_thread_items(&timeline, tweet_id)?)
+}
+
+/// get the main tweet and the rest of its thread from its `TweetDetail` 
+/// timeline (before following any "show more" cursor)
+pub(crate) fn main_thread_items(timeline: &Timeline, tweet_id: &str
+) -> Result<Vec<TweetItem>, TweetNotFound> {
+  let tweet_groups = &timeline.entries;
 
   // find out which tweet group contains the main tweet
-  let main_tweet_index: usize = get_main_tweet_index(&tweet_groups, tweet_id);
+  let main_tweet_index: usize = get_main_tweet_index(tweet_groups, tweet_id)
+    .ok_or_else(|| TweetNotFound(tweet_id.to_string()))?;
   // get the main group tweets
-  let mut main_group_tweets: Vec<Tweet> = tweet_group_to_tweet_or_tweets(&tweet_groups[main_tweet_index]);
+  let mut main_group_tweets: Vec<TweetItem> = tweet_group_to_tweet_or_tweets(&tweet_groups[main_tweet_index]);
 
   /* ---- Examples of tweet patterns we need to match ----
 
@@ -92,7 +130,7 @@ async fn url_to_tweets_no_cursor_position(tweet_id: &str
 
   // if there is a next tweet group, get it
   // need to use `.get()` bc there might not be any replies to the main tweet
-  let mut next_group_tweets: Vec<Tweet> = match tweet_groups.get(main_tweet_index + 1) {
+  let mut next_group_tweets: Vec<TweetItem> = match tweet_groups.get(main_tweet_index + 1) {
     Some(next_group) => tweet_group_to_tweet_or_tweets(next_group),
     None => Vec::new(),
   };
@@ -106,7 +144,7 @@ async fn url_to_tweets_no_cursor_position(tweet_id: &str
   if main_tweet_index == 0 {
     // IF NEXT TWEET GROUP IS GREATER THAN ZERO (required to be able to get user)
     // AND USER IS SAME AS MAIN TWEET, IT MUST BE THE THREAD, SO APPEND TO ALL_PARSED_TWEETS
-    if next_group_tweets.len() > 0 && next_group_tweets[0].user == main_group_tweets[0].user {
+    if is_same_user(&next_group_tweets, &main_group_tweets) {
       main_group_tweets.append(&mut next_group_tweets);
     }
     return Ok(main_group_tweets);
@@ -119,63 +157,106 @@ async fn url_to_tweets_no_cursor_position(tweet_id: &str
   // C) TWEET IS THREADED REPLY IF PREV IS DIFF USER AND POST IS SAME USER
 
   let prev_tweet_is_same_user = {
-    let prev_group_tweets: Vec<Tweet> = tweet_group_to_tweet_or_tweets(&tweet_groups[main_tweet_index - 1]);
-    prev_group_tweets[0].user == main_group_tweets[0].user
+    let prev_group_tweets: Vec<TweetItem> = tweet_group_to_tweet_or_tweets(&tweet_groups[main_tweet_index - 1]);
+    is_same_user(&prev_group_tweets, &main_group_tweets)
   };
 
   // if prev tweet group is same user, it is mid/end of tweet thread, so just 
   // return main tweet group (which is a single tweet)
   if prev_tweet_is_same_user {
-    return Ok(main_group_tweets);
+    Ok(main_group_tweets)
 
   // if prev tweet group is diff user, its first tweet of a reply
   } else {
     // add thread if exists
-    if next_group_tweets.len() > 0
-    && next_group_tweets[0].user == main_group_tweets[0].user {
+    if is_same_user(&next_group_tweets, &main_group_tweets) {
       main_group_tweets.append(&mut next_group_tweets);
     }
-    return Ok(main_group_tweets);
+    Ok(main_group_tweets)
   }
 }
 
-fn get_main_tweet_index(tweet_groups: &Vec<Value>, tweet_id: &str) -> usize {
-  for (i, tweet_group) in tweet_groups.clone().iter().enumerate() {
-    let entry_id = tweet_group["entryId"].as_str().unwrap();
-    // "tweet-1516856286738598375" -> "1516856286738598375"
-    let id = &entry_id[6..];
-    if id == tweet_id {
-      return i;
-    }
+/// the index of the tweet group of the main tweet, if it's in the timeline
+fn get_main_tweet_index(tweet_groups: &[TimelineEntry], tweet_id: &str) -> Option<usize> {
+  // "tweet-1516856286738598375" -> "1516856286738598375"
+  tweet_groups.iter()
+    .position(|tweet_group| tweet_group.entry_id.strip_prefix("tweet-") == Some(tweet_id))
+}
+
+/// whether the first tweet of both tweet groups are by the same user 
+/// (tombstones have no user, so never match)
+fn is_same_user(group_a: &[TweetItem], group_b: &[TweetItem]) -> bool {
+  match (group_a.first().and_then(TweetItem::as_tweet), group_b.first().and_then(TweetItem::as_tweet)) {
+    (Some(a), Some(b)) => a.user == b.user,
+    _ => false,
   }
-  return 0; // will never reach this return, but rust complains if it isn't there
 }
 
 /// get the tweet/tweets from a tweet group
 /// 
 /// the tweet group is either a single tweet, or multiple tweets
-fn tweet_group_to_tweet_or_tweets(tweet_group: &Value) -> Vec<Tweet> {
-  match tweet_group.get("content")
-  .and_then(|v| v.get("items")).and_then(|v| v.as_array()) {
+fn tweet_group_to_tweet_or_tweets(tweet_group: &TimelineEntry) -> Vec<TweetItem> {
+  match &tweet_group.content {
     /* ------if group has items (I.E. TWEET GROUP HAS MULTIPLE TWEETS)------ */
-    Some(contents) => tweet_group_to_tweets(contents),
+    EntryContent::Module(items) => tweet_group_to_tweets(items),
     /* ------if group has no items (I.E. TWEET GROUP IS JUST ONE TWEET)------ */
-    None => match parse_tweet_contents(&tweet_group["content"]["itemContent"]) {
-      Some(tweet) => Vec::from([tweet]),
-      None => Vec::new(),
-    },
+    EntryContent::Item(item) => timeline_item_to_tweet_item(item, &tweet_group.entry_id)
+      .into_iter().collect(),
+    // e.g. a "show more replies" cursor that isn't inside a module
+    EntryContent::Cursor(cursor) => vec![TweetItem::Cursor {
+      kind: cursor.cursor_type.clone(),
+      value: cursor.value.clone(),
+    }],
+    EntryContent::Other(kind) => vec![TweetItem::Unsupported { kind: kind.clone() }],
   }
 }
 
 /// loop through json tweet items to get parsed tweets
-fn tweet_group_to_tweets(tweet_group: &Vec<Value>) -> Vec<Tweet> {
-  tweet_group.iter().map(|tweet_item| {
-    parse_tweet_contents(&tweet_item["item"]["itemContent"]).unwrap()
-  }).collect()
+fn tweet_group_to_tweets(tweet_group: &[ModuleItem]) -> Vec<TweetItem> {
+  tweet_group.iter()
+    .filter_map(|module_item| timeline_item_to_tweet_item(&module_item.item, &module_item.entry_id))
+    .collect()
 }
 
-/// convert a single tweet object to a `Tweet`
-fn parse_tweet_contents(unparsed_tweet: &Value) -> Option<Tweet> {
+/// convert a timeline item to a `TweetItem`. `entry_id` is the id of the 
+/// timeline entry/module item, which is used to recover the id of tombstones
+pub(crate) fn timeline_item_to_tweet_item(item: &TimelineItem, entry_id: &str) -> Option<TweetItem> {
+  match item {
+    TimelineItem::Tweet(item_content) => match parse_tweet_contents(item_content)? {
+      TweetItem::Tombstone { text, id: None } => Some(TweetItem::Tombstone {
+        text,
+        id: entry_id_to_tweet_id(entry_id),
+      }),
+      tweet_item => Some(tweet_item),
+    },
+    // e.g. a "show more" item, which signals we need a new request at the 
+    // cursor position
+    TimelineItem::Cursor(cursor) => Some(TweetItem::Cursor {
+      kind: cursor.cursor_type.clone(),
+      value: cursor.value.clone(),
+    }),
+    TimelineItem::Other { kind, .. } => Some(TweetItem::Unsupported { kind: kind.clone() }),
+  }
+}
+
+/// get the tweet id from the id of a timeline entry/module item, e.g. 
+/// "tweet-1516856286738598375" or 
+/// "conversationthread-1516856286738598375-tweet-1516857112513548290"
+fn entry_id_to_tweet_id(entry_id: &str) -> Option<String> {
+  let (_, id) = entry_id.rsplit_once("tweet-")?;
+  match !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) {
+    true => Some(id.to_string()),
+    false => None,
+  }
+}
+
+/// convert a single tweet object to a `TweetItem::Tweet`, or a 
+/// `TweetItem::Tombstone` if the tweet can't be viewed
+/// 
+/// the id of tombstones isn't in the tweet object, so is always `None` (see 
+/// `timeline_item_to_tweet_item()`). returns `None` if there is no result, or
+/// it has no type or user
+pub(crate) fn parse_tweet_contents(unparsed_tweet: &Value) -> Option<TweetItem> {
   let unparsed_tweet = match unparsed_tweet
   // normal tweet
   .get("tweet_results").and_then(|v| v.get("result"))
@@ -183,103 +264,120 @@ fn parse_tweet_contents(unparsed_tweet: &Value) -> Option<Tweet> {
   .or(unparsed_tweet.get("result")) {
     // if tweet
     Some(unparsed_tweet) => {
-      let kind = item_type(unparsed_tweet);
-      match kind.as_str() {
+      match item_type(unparsed_tweet) {
         // normal visible tweet
-        "Tweet" => unparsed_tweet,
+        Some("Tweet") => unparsed_tweet,
         // idk why this happens, but the example is in `url_test_text_only_tweets_15()`
         // and `url_test_text_only_tweets_16()`
         // (normal-ish tweet)
-        "TweetWithVisibilityResults" => &unparsed_tweet["tweet"],
-        "TweetTombstone" => {
-          // if tweet is unable to be viewed (e.g. "You’re unable to view this Tweet 
-          // because this account owner limits who can view their Tweets. Learn more"), 
-          // unparsed_tweet["legacy"] will equal null, but we still want to tell the 
-          // user this tweet is missing, so we must create our own tweet
-          //
-          // FIXME: it is possible this will not give the desirable behavior if 
-          // the non-viewable tweet is a deleted tweet, and is from the same 
-          // user as the main tweet (while this is the first tweet in the 
-          // thread), thus the user match check will assume it is not the same 
-          // user, and not add ANY of the thread tweets, but we still want the 
-          // thread tweets
-          // perhaps i just need to check the next tweet if first tweet checked 
-          // has user="hidden"
-          return create_missing_tweet(unparsed_tweet);
-        },
-        _ => panic!("idk what type this is: {kind}"),
-      }
-    },
-    // if "Show more" button
-    None =>  {
-      // if its a "show more" item, add as special last tweet (to signal we need 
-      // a new request at the cursor position), then break
-      let kind = item_type(unparsed_tweet);
-      if kind == "TimelineTimelineCursor".to_string() {
-        let show_more_cursor = unparsed_tweet["value"].as_str().unwrap().to_string();
-        return Some(Tweet {
-          id: "more_tweets_in_thread".to_string(),
-          user: "".to_string(),
-          text: show_more_cursor, 
-          media: None, urls: None, quote: None, thread_id: None, extra: None
-        });
-      } else {
-        // FIXME: does this ever trigger??? (since i unwrap all 
-        // `Option<Tweet>`s, i should find out soon enough)
-        return None;
+        Some("TweetWithVisibilityResults") => &unparsed_tweet["tweet"],
+        // if tweet is unable to be viewed (e.g. "You’re unable to view this Tweet 
+        // because this account owner limits who can view their Tweets. Learn more"), 
+        // unparsed_tweet["legacy"] will equal null
+        Some("TweetTombstone") => return Some(parse_tombstone(unparsed_tweet)),
+        Some(kind) => return Some(TweetItem::Unsupported { kind: kind.to_string() }),
+        // no type (e.g. the result is unavailable, or a different shape), so
+        // skip it
+        None => return None,
       }
     },
+    None => return None,
   };
-  let id = unparsed_tweet["legacy"]["id_str"].as_str().unwrap().to_string();
-  let user = unparsed_tweet["core"]["user_results"]["result"]["legacy"]["screen_name"].as_str().unwrap().to_string();
-  let text = unparsed_tweet["legacy"]["full_text"].as_str().unwrap().to_string();
-  let media = parse_media(&unparsed_tweet["legacy"]);
-  let urls = parse_urls(&unparsed_tweet["legacy"]);
-  let quote = unparsed_tweet.get("quoted_status_result")
-    .and_then(|quote_contents| parse_tweet_contents(quote_contents))
-    .and_then(|tweet| Some(Box::new(tweet)));
-  let thread_id = unparsed_tweet["legacy"].get("self_thread")
-    .and_then(|o| Some(o.get("id_str").unwrap().as_str().unwrap().to_string()));
-  return Some(Tweet { id, user, text, media, urls, quote, thread_id, extra: None })
+  let user_json = &unparsed_tweet["core"]["user_results"]["result"]["legacy"];
+  let user = user_json["screen_name"].as_str()?;
+  // if this is a retweet, return the retweeted tweet (same as `query_to_tweets()`)
+  if let Some(retweeted) = unparsed_tweet["legacy"].get("retweeted_status_result") {
+    return match parse_tweet_contents(retweeted)? {
+      TweetItem::Tweet(mut tweet) => {
+        let legacy = &unparsed_tweet["legacy"];
+        tweet.retweeted_by = Some(vec![Retweet {
+          user: user.to_string(),
+          id: legacy["id_str"].as_str().unwrap_or_default().to_string(),
+          created_at: legacy["created_at"].as_str().and_then(parse_twitter_date),
+        }]);
+        Some(TweetItem::Tweet(tweet))
+      },
+      tweet_item => Some(tweet_item),
+    };
+  }
+  let mut tweet = parse_legacy_tweet(&unparsed_tweet["legacy"], user, &unparsed_tweet["views"]);
+  tweet.author = parse_author(user_json);
+  tweet.card = parse_card(&unparsed_tweet["card"]);
+  // if the quoted tweet can't be viewed, `quote` is `None` (but `quoted_id` is 
+  // still set)
+  tweet.quote = unparsed_tweet.get("quoted_status_result")
+    .and_then(parse_tweet_contents)
+    .and_then(TweetItem::into_tweet)
+    .map(Box::new);
+  Some(TweetItem::Tweet(tweet))
 }
 
-/// get the type of item in twitter raw json
-/// 
-/// note: this will be unable to find a type of a quoted tweet
-fn item_type(item: &Value) -> String {
-  match item["entryType"].as_str()
-  .or(item["itemType"].as_str())
-  // it seems typename is not returned from my requests, but does in 
-  // webinspector so i prob have some header option turned off
-  .or(item["__typename"].as_str()) {
-    Some(v) => v,
-    None => panic!("can't find type:\n{item}"),
-  }.to_string()
+/// get the type of a tweet result in twitter raw json, e.g. "Tweet"
+fn item_type(item: &Value) -> Option<&str> {
+  item["__typename"].as_str()
 }
 
-fn create_missing_tweet(unparsed_tweet: &Value) -> Option<Tweet> {
-  let txt = unparsed_tweet["tombstone"]["text"]["text"].as_str().unwrap();
-  Some(Tweet {
-    id: "".to_string(),
-    user: "unknown".to_string(),
-    // slice is to remove " Learn more"
-    text: format!("<<< {} >>>", &txt[..(txt.len() - 11)]),
-    media: None, urls: None, quote: None, thread_id: None, extra: None,
-  })
+fn parse_tombstone(unparsed_tweet: &Value) -> TweetItem {
+  let text = unparsed_tweet["tombstone"]["text"]["text"].as_str().unwrap_or_default();
+  TweetItem::Tombstone {
+    // rm " Learn more"
+    text: text.strip_suffix(" Learn more").unwrap_or(text).to_string(),
+    id: None,
+  }
+}
+
+/* ------------------------------- url_to_tweet ------------------------------- */
+
+/// get only the tweet at a tweet url (or bare tweet id), without the rest of 
+/// its thread
+///
+/// returns a `TweetNotFound` if the tweet isn't in the response
+pub async fn url_to_tweet(url: &str) -> Result<Tweet, Box<dyn Error>> {
+  let tweet_ref = TweetRef::parse(url)?;
+  let timeline = Timeline::from_instructions(&id_fetch(&tweet_ref.id, "", false).await?);
+  let main_tweet_index = get_main_tweet_index(&timeline.entries, &tweet_ref.id)
+    .ok_or_else(|| TweetNotFound(tweet_ref.id.clone()))?;
+  tweet_group_to_tweet_or_tweets(&timeline.entries[main_tweet_index])
+    .into_iter()
+    .filter_map(TweetItem::into_tweet)
+    .find(|tweet| tweet.id == tweet_ref.id)
+    .ok_or_else(|| "tweet can't be viewed".into())
+}
+
+/* ----------------------------- url_to_metrics ----------------------------- */
+
+/// get the engagement counts (retweets, replies, quotes, likes, bookmarks, 
+/// views) of the tweet at a tweet url (or bare tweet id)
+pub async fn url_to_metrics(url: &str) -> Result<TweetMetrics, Box<dyn Error>> {
+  url_to_tweet(url).await?.metrics
+    .ok_or_else(|| "tweet can't be viewed".into())
 }
 
 /* ----------------------- url_to_recommended_tweets ----------------------- */
 
+/// get the tweets twitter recommends below a tweet url (or bare tweet id)
+///
+/// returns a `TweetRefError` if the url isn't a link to a tweet, or an error
+/// if the response has no module of recommended tweets
 pub async fn url_to_recommended_tweets(url: &str) -> Result<Vec<Tweet>, Box<dyn Error>> {
-  let id_from_input_url = url.split("/").collect::<Vec<&str>>()[5];
-  let tweet_groups_json = id_fetch(&id_from_input_url, 
-    "", true).await?;
-  let tweet_groups = tweet_groups_json.as_array().unwrap();
-
-  // all recommended tweets are in second-last tweet_group item
-  let recommended_tweets = tweet_groups[&tweet_groups.len() - 2]
-    .get("content").and_then(|v| v.get("items"))
-    .and_then(|v| v.as_array()).unwrap();
-  
-  Ok(tweet_group_to_tweets(recommended_tweets))
+  let tweet_ref = TweetRef::parse(url)?;
+  let timeline = Timeline::from_instructions(
+    &id_fetch(&tweet_ref.id, "", true).await?);
+  recommended_tweets(&timeline)
+}
+
+/// get the tweets in the module of recommended tweets, whose entry id is e.g.
+/// "tweetdetailrelatedtweets-1516856286738598375" (other modules are reply
+/// threads, which can come before or after it)
+pub(crate) fn recommended_tweets(timeline: &Timeline) -> Result<Vec<Tweet>, Box<dyn Error>> {
+  let recommended_tweets = timeline.entries.iter()
+    .filter(|entry| entry.entry_id.starts_with("tweetdetailrelatedtweets-"))
+    .find_map(|entry| match &entry.content {
+      EntryContent::Module(items) => Some(items),
+      _ => None,
+    }).ok_or("no recommended tweets in the response")?;
+
+  Ok(tweet_group_to_tweets(recommended_tweets).into_iter()
+    .filter_map(TweetItem::into_tweet)
+    .collect())
 }
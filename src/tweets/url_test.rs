@@ -1,4 +1,9 @@
-use super::url::{url_to_tweets, url_to_recommended_tweets};
+use serde_json::{json, Value};
+use crate::{timeline::Timeline, types::TweetItem};
+use super::url::{
+  url_to_tweets, url_to_recommended_tweets, url_to_metrics, recommended_tweets, parse_tweet_contents,
+  main_thread_items, TweetNotFound,
+};
 
 
 /* ---------------------------- text only tweets ---------------------------- */
@@ -157,6 +162,17 @@ async fn url_test_text_only_tweets_16() {
   assert_eq!(tweets.len(), 2);
 }
 
+/* ---------------------------------metrics--------------------------------- */
+
+#[tokio::test]
+async fn url_test_metrics() {
+  test_error_msg_info("url_to_metrics", "thread, 1st-tweet");
+  let url = "https://twitter.com/epolynya/status/1513868637307691009";
+  let metrics = url_to_metrics(url).await.unwrap();
+  assert!(metrics.faves > 0);
+  assert!(metrics.retweets > 0);
+}
+
 /* -------------------------------recommended------------------------------- */
 
 #[tokio::test]
@@ -168,6 +184,75 @@ async fn url_test_recommended_tweets() {
   assert!(tweets.len() > 10); // should be 24, so i prob need to join cursor query
 }
 
+/// a `TimelineTimelineModule` entry of tweets by "A"
+fn module(entry_id: &str, tweet_ids: &[&str]) -> Value {
+  let items: Vec<Value> = tweet_ids.iter().map(|id| json!({ "entryId": format!("{entry_id}-tweet-{id}"), "item": {
+    "itemContent": { "itemType": "TimelineTweet", "tweet_results": { "result": {
+      "__typename": "Tweet",
+      "core": { "user_results": { "result": { "legacy": { "screen_name": "A" } } } },
+      "legacy": { "id_str": id, "full_text": format!("tweet {id}") },
+    } } },
+  } })).collect();
+  json!({ "entryId": entry_id, "content": { "entryType": "TimelineTimelineModule", "items": items } })
+}
+
+#[test]
+fn url_test_recommended_module() {
+  test_error_msg_info("recommended_tweets", "reply threads before and after the recommendations");
+  let timeline = Timeline::from_instructions(&json!([{ "type": "TimelineAddEntries", "entries": [
+    module("conversationthread-2", &["2"]),
+    module("tweetdetailrelatedtweets-1", &["10", "11"]),
+    module("conversationthread-3", &["3"]),
+  ] }]));
+  let ids: Vec<String> = recommended_tweets(&timeline).unwrap().into_iter().map(|tweet| tweet.id).collect();
+  assert_eq!(ids, vec!["10", "11"]);
+
+  // a conversation without recommendations isn't mistaken for one
+  let timeline = Timeline::from_instructions(&json!([{ "type": "TimelineAddEntries", "entries": [
+    module("conversationthread-2", &["2"]),
+  ] }]));
+  assert_eq!(recommended_tweets(&timeline).unwrap_err().to_string(), "no recommended tweets in the response");
+}
+
+/* ---------------------------------parsing--------------------------------- */
+
+#[test]
+fn url_test_result_types() {
+  test_error_msg_info("parse_tweet_contents", "results with and without a __typename");
+  let legacy = json!({ "id_str": "1", "full_text": "hi" });
+  let user = json!({ "user_results": { "result": { "legacy": { "screen_name": "A" } } } });
+  let visibility = json!({ "tweet_results": { "result": {
+    "__typename": "TweetWithVisibilityResults", "tweet": { "core": user, "legacy": legacy },
+  } } });
+  match parse_tweet_contents(&visibility) {
+    Some(TweetItem::Tweet(tweet)) => assert_eq!((tweet.id.as_str(), tweet.user.as_str()), ("1", "A")),
+    item => panic!("expected a tweet, got {item:?}"),
+  }
+  // unknown types are unsupported, and results without a type or user are
+  // skipped instead of panicking
+  let other = json!({ "tweet_results": { "result": { "__typename": "TweetUnavailable" } } });
+  assert_eq!(parse_tweet_contents(&other), Some(TweetItem::Unsupported { kind: "TweetUnavailable".to_string() }));
+  assert_eq!(parse_tweet_contents(&json!({ "tweet_results": { "result": {} } })), None);
+  let no_user = json!({ "tweet_results": { "result": { "__typename": "Tweet", "legacy": legacy } } });
+  assert_eq!(parse_tweet_contents(&no_user), None);
+}
+
+#[test]
+fn url_test_main_tweet_missing() {
+  test_error_msg_info("main_thread_items", "deleted/suspended/protected tweet, empty timeline");
+  let timeline = Timeline::from_instructions(&json!([{ "type": "TimelineAddEntries", "entries": [] }]));
+  assert_eq!(main_thread_items(&timeline, "1"), Err(TweetNotFound("1".to_string())));
+
+  let mut entry = module("x", &["1"])["content"]["items"][0]["item"].take();
+  entry["entryType"] = json!("TimelineTimelineItem");
+  let timeline = Timeline::from_instructions(&json!([{ "type": "TimelineAddEntries", "entries": [
+    { "entryId": "tweet-1", "content": entry },
+  ] }]));
+  let items = main_thread_items(&timeline, "1").unwrap();
+  assert_eq!(items.iter().map(|item| item.id()).collect::<Vec<_>>(), vec![Some("1")]);
+  assert_eq!(main_thread_items(&timeline, "2").unwrap_err().to_string(), "tweet not found or unavailable: 2");
+}
+
 fn test_error_msg_info(function: &str, input_type: &str) {
   println!("┌─━\n│ {function}()\n│  └─ {input_type}\n└─━");
 }
\ No newline at end of file
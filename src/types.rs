@@ -1,46 +1,670 @@
-#[derive(Debug, Clone)]
+use std::{collections::BTreeMap, ops::Range, time::SystemTime};
+use serde_json::{json, Value};
+use crate::{
+  snowflake::TweetId,
+  date::{format_iso8601, format_rfc2822},
+  timeline::CursorType,
+  tweets::text::decode_html_entities,
+};
+
+/// a tweet. tweets from all endpoints (`url_to_tweets()`, `query_to_tweets()`, 
+/// etc.) are parsed by the same converter, so have the same fields filled in
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tweet {
   /// id number of tweet (last part of url)
   pub id: String,
   /// username of the account who posted the tweet
   pub user: String,
+  /// display name and avatar of the account who posted the tweet
+  pub author: Option<TweetAuthor>,
   /// the text of the tweet
   pub text: String,
+  /// the part of `text` twitter displays (i.e. without the leading "@user" 
+  /// mentions of replies, and the trailing media link), see `display_text()`
+  pub display_text_range: Option<TextRange>,
+  /// hashtags, mentions and cashtags in `text` (urls are in `urls`)
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub entities: TweetEntities,
+  /// when the tweet was posted (parsed from `created_at`). `None` for tweets
+  /// that can't be viewed
+  #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_format::option_time"))]
+  pub created_at: Option<SystemTime>,
   pub media: Option<Vec<TweetMedia>>,
   pub urls: Option<Vec<TweetURLs>>,
   pub quote: Option<Box<Tweet>>,
+  /// id of the quoted tweet. this is set even if the quoted tweet can't be 
+  /// viewed (in which case `quote` is `None`)
+  pub quoted_id: Option<String>,
   /// if the tweet is part of a thread, the id of the first tweet in the thread
   pub thread_id: Option<String>,
-  pub extra: Option<TweetExtra>,
+  /// id of the tweet this tweet is replying to
+  pub in_reply_to_status_id: Option<String>,
+  /// id of the account this tweet is replying to
+  pub in_reply_to_user_id: Option<String>,
+  /// username of the account this tweet is replying to
+  pub in_reply_to_screen_name: Option<String>,
+  /// usernames the reply is addressed to, i.e. the "@user" mentions twitter 
+  /// hides at the start of replies (in order). `None` if the tweet isn't a 
+  /// reply
+  pub reply_mentions: Option<Vec<String>>,
+  /// id of the first tweet of the conversation this tweet is part of (the 
+  /// tweet's own id if it isn't a reply)
+  pub conversation_id: Option<String>,
+  /// language of the tweet detected by twitter, e.g. "en" ("und" if unknown)
+  pub lang: Option<String>,
+  /// engagement counts. `None` for tweets that can't be viewed
+  pub metrics: Option<TweetMetrics>,
+  /// link preview, poll, etc. attached to the tweet
+  pub card: Option<TweetCard>,
+  /// the retweets of this tweet that were fetched (e.g. a search matching the 
+  /// retweets), in timeline order. `None` if it was fetched directly
+  pub retweeted_by: Option<Vec<Retweet>>,
+}
+
+impl Tweet {
+  /// the id as a `TweetId` (`None` for tweets without an id, e.g. tombstones)
+  pub fn tweet_id(&self) -> Option<TweetId> {
+    self.id.parse().ok()
+  }
+
+  /// the part of `text` twitter displays (see `display_text_range`), with 
+  /// html entities decoded
+  pub fn display_text(&self) -> String {
+    let text = decode_html_entities(&self.text);
+    match self.display_text_range.and_then(|range| range.slice(&text)) {
+      Some(display_text) => display_text.to_string(),
+      None => text,
+    }
+  }
+
+  /// whether the tweet is a reply to another tweet
+  pub fn is_reply(&self) -> bool {
+    self.in_reply_to_status_id.is_some()
+  }
+
+  /// `created_at` as ISO 8601, e.g. "2018-10-10T20:19:24Z"
+  pub fn date_iso8601(&self) -> Option<String> {
+    self.created_at.map(format_iso8601)
+  }
+
+  /// `created_at` as RFC 2822, e.g. "Wed, 10 Oct 2018 20:19:24 +0000"
+  pub fn date_rfc2822(&self) -> Option<String> {
+    self.created_at.map(format_rfc2822)
+  }
+
+  /// merge in `other` (the same tweet, fetched again): adds the retweets this 
+  /// tweet doesn't already have, and the quoted tweet if this one is missing it
+  pub fn merge(&mut self, other: &Tweet) {
+    if self.quote.is_none() {
+      self.quote = other.quote.clone();
+    }
+    self.merge_retweets(other);
+  }
+
+  /// add the retweets of `other` (the same tweet, fetched again) that this 
+  /// tweet doesn't already have
+  pub fn merge_retweets(&mut self, other: &Tweet) {
+    let other_retweets = match &other.retweeted_by {
+      Some(retweets) => retweets,
+      None => return,
+    };
+    let retweets = self.retweeted_by.get_or_insert_with(Vec::new);
+    for retweet in other_retweets {
+      if !retweets.iter().any(|r| r.id == retweet.id) {
+        retweets.push(retweet.clone());
+      }
+    }
+  }
+
+  /// the tweet as a json object with the commonly used fields (e.g. for 
+  /// sending to a webhook). dates are ISO 8601, and cards and entity ranges 
+  /// are left out
+  pub fn to_json(&self) -> Value {
+    json!({
+      "id": self.id,
+      "url": format!("https://twitter.com/{}/status/{}", self.user, self.id),
+      "user": self.user,
+      "author": self.author.as_ref().map(|author| json!({
+        "name": author.name,
+        "avatar_url": author.avatar_url,
+      })),
+      "text": self.display_text(),
+      "created_at": self.date_iso8601(),
+      "lang": self.lang,
+      "hashtags": self.entities.hashtags.iter().map(|hashtag| &hashtag.text).collect::<Vec<_>>(),
+      "mentions": self.entities.mentions.iter().map(|mention| &mention.screen_name).collect::<Vec<_>>(),
+      "urls": self.urls.iter().flatten().map(|url| json!({
+        "shortened_url": url.shortened_url,
+        "full_url": url.full_url,
+      })).collect::<Vec<Value>>(),
+      "media": self.media.iter().flatten().map(|media| json!({
+        "id": media.id,
+        "type": match media.kind {
+          MediaKind::Photo => "photo",
+          MediaKind::Video(_) => "video",
+          MediaKind::AnimatedGif(_) => "animated_gif",
+        },
+        "url": media.video_url().unwrap_or(&media.full_img_url),
+        "alt_text": media.alt_text,
+      })).collect::<Vec<Value>>(),
+      "quote": self.quote.as_ref().map(|quote| quote.to_json()),
+      "quoted_id": self.quoted_id,
+      "in_reply_to_status_id": self.in_reply_to_status_id,
+      "in_reply_to_screen_name": self.in_reply_to_screen_name,
+      "conversation_id": self.conversation_id,
+      "metrics": self.metrics.as_ref().map(|metrics| json!({
+        "retweets": metrics.retweets,
+        "replies": metrics.replies,
+        "quotes": metrics.quotes,
+        "faves": metrics.faves,
+        "bookmarks": metrics.bookmarks,
+        "views": metrics.views,
+      })),
+      "retweeted_by": self.retweeted_by.as_ref().map(|retweets| retweets.iter().map(|retweet| json!({
+        "user": retweet.user,
+        "id": retweet.id,
+        "created_at": retweet.created_at.map(format_iso8601),
+      })).collect::<Vec<Value>>()),
+    })
+  }
+}
+
+/// the profile of the account who posted a tweet (the username is `Tweet::user`)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TweetAuthor {
+  /// display name, e.g. "Balaji"
+  pub name: String,
+  /// url of the profile picture (48x48)
+  pub avatar_url: Option<String>,
 }
 
-// FIXME: make Tweet the only tweet struct, then add substructs as optional, e.g.
-// also can change `quote` prop to `Option<Box<Tweet>>` to fix error from `Option<Tweet>` 
+/// a retweet of a tweet
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Retweet {
+  /// username of the account that retweeted
+  pub user: String,
+  /// id of the retweet itself (not of the retweeted tweet)
+  pub id: String,
+  /// when it was retweeted
+  #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_format::option_time"))]
+  pub created_at: Option<SystemTime>,
+}
+
+impl Retweet {
+  /// from the tweet item of a retweet (the one whose text starts with 
+  /// "RT @user: ")
+  pub fn from_tweet(retweet: &Tweet) -> Retweet {
+    Retweet {
+      user: retweet.user.clone(),
+      id: retweet.id.clone(),
+      created_at: retweet.created_at,
+    }
+  }
+}
+
+/// an item of a tweet timeline (e.g. a tweet thread, or a conversation)
+// almost all items are tweets, so boxing the tweet would just add an allocation
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum TweetItem {
+  Tweet(Tweet),
+  /// a tweet that can't be viewed (e.g. deleted, or from a protected account)
+  Tombstone {
+    /// the reason it can't be viewed, e.g. "This Tweet was deleted by the 
+    /// Tweet author."
+    text: String,
+    /// id of the tweet, if it can be recovered from the timeline entry
+    id: Option<String>,
+  },
+  /// a cursor to load more tweets at this position (e.g. a "show more" button)
+  Cursor { kind: CursorType, value: String },
+  /// any other item (e.g. a user to follow), with its raw type
+  Unsupported { kind: String },
+}
+
+impl TweetItem {
+  /// id of the tweet or tombstone
+  pub fn id(&self) -> Option<&str> {
+    match self {
+      TweetItem::Tweet(tweet) => Some(&tweet.id),
+      TweetItem::Tombstone { id, .. } => id.as_deref(),
+      _ => None,
+    }
+  }
+
+  pub fn as_tweet(&self) -> Option<&Tweet> {
+    match self {
+      TweetItem::Tweet(tweet) => Some(tweet),
+      _ => None,
+    }
+  }
+
+  pub fn into_tweet(self) -> Option<Tweet> {
+    match self {
+      TweetItem::Tweet(tweet) => Some(tweet),
+      _ => None,
+    }
+  }
+}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TweetMedia {
+  /// id of the media (`id_str`)
+  pub id: String,
   /// the twitter shortened url
   pub shortened_img_url: String,
-  /// the original image url
+  /// the original image url (for videos and gifs, the thumbnail)
   pub full_img_url: String,
-  /// `photo` or `video` or `animated_gif`
-  pub kind: String,
-  pub video_url: Option<String>,
+  /// position of `shortened_img_url` in the tweet text
+  pub range: Option<TextRange>,
+  /// description of the media added by the author
+  pub alt_text: Option<String>,
+  /// width and height of the original image/video
+  pub original_size: Option<(u64, u64)>,
+  /// areas of the original image that twitter uses when cropping it to fit 
+  /// different aspect ratios (`focus_rects`)
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub crops: Vec<MediaCrop>,
+  /// the sizes the image is available in (e.g. "thumb", "small", "large"), 
+  /// see `TweetMedia::sized_img_url()`
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub sizes: Vec<MediaSize>,
+  pub kind: MediaKind,
+}
+
+impl TweetMedia {
+  /// url of the image in one of its `sizes` (e.g. "small"), or "orig" for the
+  /// original resolution
+  pub fn sized_img_url(&self, size: &str) -> String {
+    format!("{}?name={size}", self.full_img_url)
+  }
+
+  pub fn video_info(&self) -> Option<&VideoInfo> {
+    match &self.kind {
+      MediaKind::Photo => None,
+      MediaKind::Video(info) | MediaKind::AnimatedGif(info) => Some(info),
+    }
+  }
+
+  /// url of the highest bitrate mp4 of a video or gif
+  pub fn video_url(&self) -> Option<&str> {
+    self.video_info()?.best_mp4().map(|variant| variant.url.as_str())
+  }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum MediaKind {
+  Photo,
+  Video(VideoInfo),
+  /// gifs are stored as (silent, looping) mp4 videos
+  AnimatedGif(VideoInfo),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VideoInfo {
+  /// `None` for gifs
+  pub duration_ms: Option<u64>,
+  /// e.g. `(16, 9)`
+  pub aspect_ratio: Option<(u64, u64)>,
+  /// every format the video is available in, including the hls (`.m3u8`) 
+  /// playlist
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub variants: Vec<VideoVariant>,
+}
+
+impl VideoInfo {
+  /// the mp4 variant with the highest bitrate. gifs only have one mp4 
+  /// variant, which may have no bitrate
+  pub fn best_mp4(&self) -> Option<&VideoVariant> {
+    self.variants.iter()
+      .filter(|variant| variant.content_type == "video/mp4")
+      .max_by_key(|variant| variant.bitrate.unwrap_or(0))
+  }
+
+  /// the hls playlist variant (`application/x-mpegURL`)
+  pub fn hls_playlist(&self) -> Option<&VideoVariant> {
+    self.variants.iter().find(|variant| variant.content_type == "application/x-mpegURL")
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VideoVariant {
+  pub url: String,
+  /// e.g. "video/mp4", "application/x-mpegURL"
+  pub content_type: String,
+  /// bits per second. `None` for the hls playlist (and some gifs)
+  pub bitrate: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaSize {
+  /// e.g. "thumb", "small", "medium", "large"
+  pub name: String,
+  pub width: u64,
+  pub height: u64,
+  /// "fit" (scaled to fit in the size) or "crop" (cropped to the size)
+  pub resize: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaCrop {
+  pub x: u64,
+  pub y: u64,
+  pub width: u64,
+  pub height: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TweetURLs {
   /// the twitter shortened url
   pub shortened_url: String,
   /// the original url
   pub full_url: String,
+  /// the shortened form of the original url shown in the tweet, e.g. 
+  /// "example.com/a-long-pa…"
+  pub display_url: String,
+  /// position of `shortened_url` in the tweet text
+  pub range: Option<TextRange>,
+}
+
+/// the position of an entity (hashtag, mention, url, etc.) in the tweet text
+///
+/// twitter gives the positions as indices of unicode code points (so an emoji 
+/// counts as one character), in the text *after* html entities are decoded 
+/// (e.g. "&amp;" counts as one character). to index into a rust string use 
+/// `byte_range()`, and for javascript strings use `utf16_range()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextRange {
+  pub start: usize,
+  pub end: usize,
 }
 
-/// additional attributes for `Tweet`, such as `date`, `quoted_tweet_id`, 
-/// `retweet_tweet_id`, `retweeted_by`
-#[derive(Debug, Clone)]
-pub struct TweetExtra {
-  pub date: String,
-  pub retweeted_by: Option<Vec<String>>,
+impl TextRange {
+  pub fn new(start: usize, end: usize) -> TextRange {
+    TextRange { start, end }
+  }
+
+  /// parse the `indices` array of an entity, e.g. `[0, 9]`
+  pub fn from_indices(indices: &serde_json::Value) -> Option<TextRange> {
+    let start = indices[0].as_u64()? as usize;
+    let end = indices[1].as_u64()? as usize;
+    match start <= end {
+      true => Some(TextRange { start, end }),
+      false => None,
+    }
+  }
+
+  /// the range as byte offsets into `text`. `None` if the range is past the 
+  /// end of `text`
+  pub fn byte_range(&self, text: &str) -> Option<Range<usize>> {
+    Some(char_to_byte_index(text, self.start)?..char_to_byte_index(text, self.end)?)
+  }
+
+  /// the range as utf-16 code unit offsets into `text` (as used by javascript 
+  /// strings). `None` if the range is past the end of `text`
+  pub fn utf16_range(&self, text: &str) -> Option<Range<usize>> {
+    let byte_range = self.byte_range(text)?;
+    let start = text[..byte_range.start].encode_utf16().count();
+    let len = text[byte_range].encode_utf16().count();
+    Some(start..(start + len))
+  }
+
+  /// convert a range of utf-16 code units in `text` to a `TextRange`. `None`
+  /// if the range is past the end of `text` or splits a surrogate pair
+  pub fn from_utf16_range(text: &str, range: Range<usize>) -> Option<TextRange> {
+    let mut utf16_index = 0;
+    let (mut start, mut end) = (None, None);
+    for (char_index, c) in text.chars().chain(['\0']).enumerate() {
+      if utf16_index == range.start { start = Some(char_index) }
+      if utf16_index == range.end { end = Some(char_index) }
+      utf16_index += c.len_utf16();
+    }
+    Some(TextRange { start: start?, end: end? }).filter(|r| r.start <= r.end)
+  }
+
+  /// the part of `text` in the range
+  pub fn slice<'a>(&self, text: &'a str) -> Option<&'a str> {
+    text.get(self.byte_range(text)?)
+  }
+}
+
+/// byte index of the `char_index`th char of `text` (or the length of `text` if 
+/// `char_index` is the number of chars)
+fn char_to_byte_index(text: &str, char_index: usize) -> Option<usize> {
+  text.char_indices().map(|(i, _)| i)
+    .chain([text.len()])
+    .nth(char_index)
+}
+
+/// the entities in a tweet's text, besides urls (which are in `Tweet.urls`) 
+/// and media (which are in `Tweet.media`)
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TweetEntities {
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub hashtags: Vec<Hashtag>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub mentions: Vec<Mention>,
+  /// e.g. "$TWTR" (called `symbols` by twitter)
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub cashtags: Vec<Cashtag>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hashtag {
+  /// the hashtag without the "#"
+  pub text: String,
+  pub range: TextRange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mention {
+  /// the username without the "@"
+  pub screen_name: String,
+  /// display name of the account
+  pub name: Option<String>,
+  /// id of the account
+  pub id: Option<String>,
+  pub range: TextRange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cashtag {
+  /// the symbol without the "$"
+  pub text: String,
+  pub range: TextRange,
+}
+
+/// a card attached to a tweet
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum TweetCard {
+  /// link preview (`summary` and `summary_large_image`)
+  Summary(SummaryCard),
+  /// `poll2choice_text_only`, `poll4choice_image`, etc.
+  Poll(PollCard),
+  /// embedded player (e.g. youtube videos)
+  Player(PlayerCard),
+  /// `unified_card`, e.g. app or website carousels
+  Unified(UnifiedCard),
+  /// any other card, with its raw name
+  Other { name: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SummaryCard {
+  /// whether the image is shown large (`summary_large_image`) rather than as
+  /// a thumbnail (`summary`)
+  pub large_image: bool,
+  pub title: Option<String>,
+  pub description: Option<String>,
+  pub image: Option<CardImage>,
+  /// e.g. "example.com"
+  pub domain: Option<String>,
+  /// the shortened url the card is for
+  pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PollCard {
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub choices: Vec<PollChoice>,
+  #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_format::option_time"))]
+  pub end_time: Option<SystemTime>,
+  /// when the counts were last updated
+  #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_format::option_time"))]
+  pub last_updated: Option<SystemTime>,
+  pub duration_minutes: Option<u64>,
+  /// whether the poll has ended, so the counts won't change
+  pub counts_are_final: bool,
+  /// only for image polls
+  pub image: Option<CardImage>,
+}
+
+impl PollCard {
+  pub fn total_votes(&self) -> u64 {
+    self.choices.iter().map(|choice| choice.count).sum()
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PollChoice {
+  pub label: String,
+  pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerCard {
+  pub title: Option<String>,
+  pub description: Option<String>,
+  /// url of the page to embed in an iframe
+  pub player_url: Option<String>,
+  /// width and height of the player
+  pub player_size: Option<(u64, u64)>,
+  pub image: Option<CardImage>,
+  pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnifiedCard {
+  /// e.g. "image_website", "image_carousel_app"
+  pub kind: String,
+  /// title of the first details component
+  pub title: Option<String>,
+  /// urls of the websites the card links to
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub urls: Vec<String>,
+  /// the apps the card links to
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub apps: Vec<CardApp>,
+  /// urls of the images (and video thumbnails) in the card
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub media_urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CardApp {
+  /// e.g. "com.example.app" (android) or "123456789" (ios)
+  pub id: String,
+  /// e.g. "android_app", "iphone_app"
+  pub store: String,
+  pub title: Option<String>,
+  pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CardImage {
+  pub url: String,
+  pub width: Option<u64>,
+  pub height: Option<u64>,
+}
+
+/// engagement counts of a tweet
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TweetMetrics {
+  pub retweets: u64,
+  pub replies: u64,
+  pub quotes: u64,
+  /// likes
   pub faves: u64,
+  /// only returned by graphql endpoints (e.g. `TweetDetail`)
+  pub bookmarks: Option<u64>,
+  /// only returned for tweets posted after views were added (dec 2022)
+  pub views: Option<u64>,
+}
+
+/// the profile-level tweet stats of a user, from the `TweetStats` graphql 
+/// operation, see `tweets::stats::user_tweet_stats()`
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UserTweetStats {
+  pub user_id: String,
+  /// how often the user tweets (the `tweet_frequency` stat), if returned
+  pub tweet_frequency: Option<f64>,
+  /// every numeric stat of the user's `tweet_stats`, by name (including 
+  /// `tweet_frequency`), as twitter adds stats behind new feature switches
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub stats: BTreeMap<String, f64>,
+}
+
+/// the full conversation around a tweet, see `tweets::conversation::conversation()`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conversation {
+  /// the tweets above the focal tweet, starting with the root of the 
+  /// conversation. tweets that can't be viewed are tombstones
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub ancestors: Vec<TweetItem>,
+  /// the tweet the conversation was requested for
+  pub focal: Tweet,
+  /// the replies to the focal tweet, each with their own nested replies
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub replies: Vec<ConversationNode>,
+}
+
+/// a tweet in a conversation tree, along with all replies to it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConversationNode {
+  /// a tweet or a tombstone
+  pub item: TweetItem,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub replies: Vec<ConversationNode>,
+}
+
+impl Drop for ConversationNode {
+  // drop the replies one level at a time, as dropping a very deep thread 
+  // recursively can overflow the stack
+  fn drop(&mut self) {
+    let mut replies = std::mem::take(&mut self.replies);
+    while let Some(mut reply) = replies.pop() {
+      replies.append(&mut reply.replies);
+    }
+  }
 }
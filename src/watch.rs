@@ -0,0 +1,412 @@
+//! polling searches for new tweets
+//!
+//! a `Watcher` re-runs its sources (searches, or the tweets of users) on an
+//! interval, and emits only the tweets newer than the newest one it has seen
+//! for each source. the newest id of each source (its "high-water mark") is
+//! kept in a `SinceIdStore`, so a watcher started again with the same store
+//! carries on where it stopped
+//!
+//! each poll follows the pages of the search until one reaches the mark (up
+//! to `WatcherOptions::max_pages`, after which a `WatchEvent::Gap` is emitted
+//! for the tweets that may have been skipped)
+//!
+//! a mark is only saved once the tweets up to it have been handled, i.e. on
+//! the next `Watcher::poll()` (or `Watcher::commit()`), so tweets that were
+//! fetched but not handled before a crash are emitted again on restart rather
+//! than skipped
+
+use std::{
+  collections::{HashMap, HashSet, VecDeque}, error::Error, fs, future::Future, io, path::PathBuf, pin::Pin,
+  time::Duration,
+};
+use futures_util::{stream, Stream};
+use serde_json::{Map, Value};
+use tokio::time::{sleep_until, Instant};
+use crate::{snowflake::TweetId, types::Tweet, tweets::query::{query_to_tweets_page, merge_duplicate_tweets}};
+
+type FetchResult = Result<(Vec<Tweet>, Option<String>), Box<dyn Error>>;
+type FetchFn = Box<dyn Fn(String, String) -> Pin<Box<dyn Future<Output = FetchResult>>>>;
+
+/// something to watch for new tweets
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum WatchSource {
+  /// a search query, e.g. "from:balajis -filter:replies"
+  Query(String),
+  /// the tweets and retweets of a user (by username)
+  User(String),
+}
+
+impl WatchSource {
+  /// the key of the source in the `SinceIdStore`, e.g. "query:from:balajis"
+  pub fn key(&self) -> String {
+    match self {
+      WatchSource::Query(query) => format!("query:{query}"),
+      WatchSource::User(user) => format!("user:{user}"),
+    }
+  }
+
+  /// the search query to run, only returning tweets after `since_id`
+  pub fn query(&self, since_id: Option<TweetId>) -> String {
+    let query = match self {
+      WatchSource::Query(query) => query.clone(),
+      WatchSource::User(user) => format!("from:{user} include:nativeretweets"),
+    };
+    match since_id {
+      Some(since_id) => format!("{query} since_id:{since_id}"),
+      None => query,
+    }
+  }
+}
+
+/// where the newest tweet id seen of each source is kept
+pub trait SinceIdStore {
+  fn get(&self, key: &str) -> io::Result<Option<TweetId>>;
+  fn set(&mut self, key: &str, id: TweetId) -> io::Result<()>;
+}
+
+/// keeps the ids in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemorySinceIdStore {
+  ids: HashMap<String, TweetId>,
+}
+
+impl MemorySinceIdStore {
+  pub fn new() -> MemorySinceIdStore {
+    MemorySinceIdStore::default()
+  }
+}
+
+impl SinceIdStore for MemorySinceIdStore {
+  fn get(&self, key: &str) -> io::Result<Option<TweetId>> {
+    Ok(self.ids.get(key).copied())
+  }
+
+  fn set(&mut self, key: &str, id: TweetId) -> io::Result<()> {
+    self.ids.insert(key.to_string(), id);
+    Ok(())
+  }
+}
+
+/// keeps the ids in a json file (an object of source key to id), which is
+/// rewritten on every change
+#[derive(Debug, Clone)]
+pub struct JsonFileSinceIdStore {
+  path: PathBuf,
+  ids: HashMap<String, TweetId>,
+}
+
+impl JsonFileSinceIdStore {
+  /// load the ids from `path` (if it exists)
+  pub fn open(path: impl Into<PathBuf>) -> io::Result<JsonFileSinceIdStore> {
+    let path = path.into();
+    let ids = match fs::read_to_string(&path) {
+      Ok(text) => {
+        let json: Value = serde_json::from_str(&text)
+          .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        json.as_object().into_iter().flatten()
+          .filter_map(|(key, id)| Some((key.clone(), id.as_str()?.parse().ok()?)))
+          .collect()
+      },
+      Err(error) if error.kind() == io::ErrorKind::NotFound => HashMap::new(),
+      Err(error) => return Err(error),
+    };
+    Ok(JsonFileSinceIdStore { path, ids })
+  }
+}
+
+impl SinceIdStore for JsonFileSinceIdStore {
+  fn get(&self, key: &str) -> io::Result<Option<TweetId>> {
+    Ok(self.ids.get(key).copied())
+  }
+
+  fn set(&mut self, key: &str, id: TweetId) -> io::Result<()> {
+    self.ids.insert(key.to_string(), id);
+    // ids are stored as strings, as they don't fit in a js number
+    let json: Map<String, Value> = self.ids.iter()
+      .map(|(key, id)| (key.clone(), Value::String(id.to_string())))
+      .collect();
+    // write to a temporary file first, so the file is never half written
+    let tmp_path = self.path.with_extension("tmp");
+    fs::write(&tmp_path, Value::Object(json).to_string())?;
+    fs::rename(&tmp_path, &self.path)
+  }
+}
+
+/// options for a `Watcher`
+#[derive(Debug, Clone)]
+pub struct WatcherOptions {
+  /// time between polls of each source
+  pub interval: Duration,
+  /// time to wait before retrying a source after its first error. doubles
+  /// with each error in a row
+  pub initial_backoff: Duration,
+  /// the longest time to wait before retrying a source
+  pub max_backoff: Duration,
+  /// whether to emit the tweets found the first time a source is polled
+  /// (when there is no id for it in the store yet). if `false`, they are only
+  /// used to set the high-water mark
+  pub emit_existing: bool,
+  /// the most pages of search results to fetch per poll of a source. if the
+  /// last of them still has new tweets, a `WatchEvent::Gap` is emitted
+  pub max_pages: usize,
+}
+
+impl Default for WatcherOptions {
+  fn default() -> WatcherOptions {
+    WatcherOptions {
+      interval: Duration::from_secs(5 * 60),
+      initial_backoff: Duration::from_secs(30),
+      max_backoff: Duration::from_secs(30 * 60),
+      emit_existing: true,
+      max_pages: 10,
+    }
+  }
+}
+
+/// what a `Watcher` emits
+// almost all events are tweets, so boxing the tweet would just add an allocation
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum WatchEvent {
+  /// a tweet newer than any seen before from the source
+  Tweet { source: WatchSource, tweet: Tweet },
+  /// polling the source (or saving its high-water mark) failed. it is polled
+  /// again after `retry_in`
+  Error { source: WatchSource, error: Box<dyn Error>, retry_in: Duration },
+  /// there were more than `WatcherOptions::max_pages` pages of new tweets, so
+  /// tweets newer than `since_id` and older than `until_id` may have been
+  /// missed. emitted before the tweets of the poll
+  Gap { source: WatchSource, since_id: TweetId, until_id: TweetId },
+}
+
+struct SourceState {
+  source: WatchSource,
+  next_poll: Instant,
+  /// errors in a row
+  failures: u32,
+  /// the high-water mark of the tweets emitted so far (`None` until the
+  /// source is first polled, when it is read from the store)
+  since_id: Option<TweetId>,
+  /// whether `since_id` is newer than the mark in the store
+  uncommitted: bool,
+}
+
+/// polls sources for new tweets, see the module docs
+pub struct Watcher<S: SinceIdStore> {
+  sources: Vec<SourceState>,
+  store: S,
+  options: WatcherOptions,
+  fetch: FetchFn,
+}
+
+impl<S: SinceIdStore> Watcher<S> {
+  pub fn new(sources: Vec<WatchSource>, store: S, options: WatcherOptions) -> Watcher<S> {
+    Watcher::with_fetch(sources, store, options, |query, cursor| async move {
+      query_to_tweets_page(&query, &cursor).await
+    })
+  }
+
+  /// a watcher that uses `fetch` to run the search queries (see
+  /// `WatchSource::query()`). it is given the query and a cursor ("" for the
+  /// first page), and returns the page's tweets and the cursor of the next page
+  pub fn with_fetch<F, Fut>(sources: Vec<WatchSource>, store: S, options: WatcherOptions, fetch: F
+  ) -> Watcher<S>
+  where
+    F: Fn(String, String) -> Fut + 'static,
+    Fut: Future<Output = FetchResult> + 'static,
+  {
+    let now = Instant::now();
+    Watcher {
+      sources: sources.into_iter()
+        .map(|source| SourceState { source, next_poll: now, failures: 0, since_id: None, uncommitted: false })
+        .collect(),
+      store,
+      options,
+      fetch: Box::new(move |query, cursor| Box::pin(fetch(query, cursor))),
+    }
+  }
+
+  /// the store of high-water marks
+  pub fn store(&self) -> &S {
+    &self.store
+  }
+
+  pub fn has_sources(&self) -> bool {
+    !self.sources.is_empty()
+  }
+
+  /// save the high-water marks of the tweets returned by the last `poll()`,
+  /// once they have been handled
+  ///
+  /// `poll()` does this itself before polling again, so this is only needed
+  /// to save the marks without polling, e.g. before stopping
+  pub fn commit(&mut self) -> io::Result<()> {
+    for i in 0..self.sources.len() {
+      self.commit_state(i)?;
+    }
+    Ok(())
+  }
+
+  /// save the high-water mark of one source, like `commit()`, e.g. once its
+  /// tweets have been delivered
+  pub fn commit_source(&mut self, source: &WatchSource) -> io::Result<()> {
+    for i in 0..self.sources.len() {
+      if self.sources[i].source == *source {
+        self.commit_state(i)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn commit_state(&mut self, i: usize) -> io::Result<()> {
+    let state = &mut self.sources[i];
+    if let (true, Some(since_id)) = (state.uncommitted, state.since_id) {
+      self.store.set(&state.source.key(), since_id)?;
+      state.uncommitted = false;
+    }
+    Ok(())
+  }
+
+  /// save the high-water marks of the tweets returned by the last call, then
+  /// wait until a source is due, and poll every due source once, returning
+  /// the new tweets (oldest first) and errors
+  pub async fn poll(&mut self) -> Vec<WatchEvent> {
+    let next_poll = match self.sources.iter().map(|state| state.next_poll).min() {
+      Some(next_poll) => next_poll,
+      None => return Vec::new(),
+    };
+    // the tweets returned by the last call have been handled, so their marks
+    // can be saved
+    let mut commit_errors = Vec::new();
+    for i in 0..self.sources.len() {
+      if let Err(error) = self.commit_state(i) {
+        commit_errors.push((i, Box::new(error) as Box<dyn Error>));
+      }
+    }
+    sleep_until(next_poll).await;
+
+    let mut events = Vec::new();
+    let now = Instant::now();
+    for (i, error) in commit_errors {
+      // the mark is saved again on the next poll
+      let state = &self.sources[i];
+      let retry_in = state.next_poll.max(now) - now;
+      events.push(WatchEvent::Error { source: state.source.clone(), error, retry_in });
+    }
+    for i in 0..self.sources.len() {
+      if self.sources[i].next_poll > now {
+        continue;
+      }
+      match self.poll_source(i).await {
+        Ok(mut new_events) => {
+          let state = &mut self.sources[i];
+          state.failures = 0;
+          state.next_poll = now + self.options.interval;
+          events.append(&mut new_events);
+        },
+        Err(error) => {
+          let state = &mut self.sources[i];
+          // initial_backoff, doubled for each earlier error in a row, capped at
+          // max_backoff
+          let retry_in = self.options.initial_backoff
+            .checked_mul(2u32.saturating_pow(state.failures))
+            .map_or(self.options.max_backoff, |backoff| backoff.min(self.options.max_backoff));
+          state.failures += 1;
+          state.next_poll = now + retry_in;
+          events.push(WatchEvent::Error { source: state.source.clone(), error, retry_in });
+        },
+      }
+    }
+    events
+  }
+
+  async fn poll_source(&mut self, i: usize) -> Result<Vec<WatchEvent>, Box<dyn Error>> {
+    let source = self.sources[i].source.clone();
+    let key = source.key();
+    let since_id = match self.sources[i].since_id {
+      Some(since_id) => Some(since_id),
+      None => self.store.get(&key)?,
+    };
+    let query = source.query(since_id);
+    let is_new = |tweet: &Tweet| newest_id(tweet).map_or(false, |id| since_id.map_or(true, |since_id| id > since_id));
+
+    // follow the pages until one reaches `since_id`. the first poll of a
+    // source only needs the newest page
+    let mut tweets = Vec::new();
+    let mut cursor = String::new();
+    let mut seen_cursors = HashSet::new();
+    let mut pages = 0;
+    let mut gap = false;
+    loop {
+      let (page, next_cursor) = (self.fetch)(query.clone(), cursor).await?;
+      pages += 1;
+      let has_new = page.iter().any(is_new);
+      let reached = page.iter().any(|tweet| !is_new(tweet));
+      tweets.extend(page);
+      cursor = match next_cursor {
+        Some(next_cursor) if since_id.is_some() && has_new && !reached && seen_cursors.insert(next_cursor.clone()) =>
+          next_cursor,
+        _ => break,
+      };
+      if pages >= self.options.max_pages {
+        gap = true;
+        break;
+      }
+    }
+
+    // search can return tweets from before `since_id` (e.g. retweets of old
+    // tweets, whose retweet is new), so compare by position
+    let mut new_tweets: Vec<(TweetId, Tweet)> = merge_duplicate_tweets(tweets).into_iter()
+      .filter_map(|tweet| Some((newest_id(&tweet)?, tweet)))
+      .filter(|(id, _)| since_id.map_or(true, |since_id| *id > since_id))
+      .collect();
+    new_tweets.sort_by_key(|(id, _)| *id);
+
+    let newest = match new_tweets.last() {
+      Some((newest, _)) => *newest,
+      None => return Ok(Vec::new()),
+    };
+    let state = &mut self.sources[i];
+    state.since_id = Some(newest);
+    if since_id.is_none() && !self.options.emit_existing {
+      // nothing is emitted, so there's nothing to wait to be handled
+      self.store.set(&key, newest)?;
+      return Ok(Vec::new());
+    }
+    state.uncommitted = true;
+    let gap = match (gap, since_id) {
+      (true, Some(since_id)) => Some(WatchEvent::Gap { source: source.clone(), since_id, until_id: new_tweets[0].0 }),
+      _ => None,
+    };
+    Ok(gap.into_iter()
+      .chain(new_tweets.into_iter().map(|(_, tweet)| WatchEvent::Tweet { source: source.clone(), tweet }))
+      .collect())
+  }
+
+  /// poll forever, as a stream of events
+  ///
+  /// the high-water marks of a poll's tweets are saved when the event after
+  /// the last of them is requested
+  pub fn into_stream(self) -> impl Stream<Item = WatchEvent> {
+    stream::unfold((self, VecDeque::new()), |(mut watcher, mut pending)| async move {
+      while pending.is_empty() {
+        if !watcher.has_sources() {
+          return None;
+        }
+        pending.extend(watcher.poll().await);
+      }
+      let event = pending.pop_front()?;
+      Some((event, (watcher, pending)))
+    })
+  }
+}
+
+/// the id of the newest of the tweet and its retweets, i.e. when it appeared
+/// in the timeline
+fn newest_id(tweet: &Tweet) -> Option<TweetId> {
+  let retweet_ids = tweet.retweeted_by.iter().flatten()
+    .filter_map(|retweet| retweet.id.parse::<TweetId>().ok());
+  tweet.tweet_id().into_iter().chain(retweet_ids).max()
+}
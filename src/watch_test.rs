@@ -0,0 +1,169 @@
+use std::{cell::RefCell, collections::{HashMap, VecDeque}, error::Error, rc::Rc, time::Duration};
+use futures_util::StreamExt;
+use crate::{
+  watch::{Watcher, WatcherOptions, WatchSource, WatchEvent, SinceIdStore, MemorySinceIdStore, JsonFileSinceIdStore},
+  snowflake::TweetId,
+  types::{Tweet, Retweet},
+  test_fixtures::{self, temp_dir},
+};
+
+type Responses = Rc<RefCell<VecDeque<Result<Vec<Tweet>, Box<dyn Error>>>>>;
+
+/// a tweet by "a", the watched user
+fn tweet(id: &str) -> Tweet {
+  test_fixtures::tweet(id, "a")
+}
+
+fn options() -> WatcherOptions {
+  WatcherOptions {
+    interval: Duration::ZERO,
+    initial_backoff: Duration::ZERO,
+    ..Default::default()
+  }
+}
+
+/// a watcher of one query, whose fetches return `responses` in order. the
+/// queries it runs are recorded in the returned vec
+fn query_watcher<S: SinceIdStore>(store: S, options: WatcherOptions, responses: Responses
+) -> (Watcher<S>, Rc<RefCell<Vec<String>>>) {
+  let queries = Rc::new(RefCell::new(Vec::new()));
+  let queries_clone = queries.clone();
+  let watcher = Watcher::with_fetch(vec![WatchSource::Query("from:a".to_string())], store, options,
+    move |query, _| {
+      queries_clone.borrow_mut().push(query);
+      let response = responses.borrow_mut().pop_front().unwrap();
+      async move { response.map(|tweets| (tweets, None)) }
+    });
+  (watcher, queries)
+}
+
+fn ids(events: &[WatchEvent]) -> Vec<&str> {
+  events.iter().filter_map(|event| match event {
+    WatchEvent::Tweet { tweet, .. } => Some(tweet.id.as_str()),
+    WatchEvent::Error { .. } | WatchEvent::Gap { .. } => None,
+  }).collect()
+}
+
+#[tokio::test]
+async fn watch_test_new_tweets() {
+  println!("Watcher::poll()  //  emits only tweets newer than the high-water mark");
+  let mut retweeted = tweet("50");
+  retweeted.retweeted_by = Some(vec![Retweet { user: "a".to_string(), id: "110".to_string(), created_at: None }]);
+  let responses: Responses = Rc::new(RefCell::new(VecDeque::from([
+    Ok(vec![tweet("102"), tweet("100")]),
+    Ok(vec![tweet("105"), retweeted, tweet("102")]),
+    Err("rate limited".into()),
+    Err("rate limited".into()),
+    Ok(vec![tweet("110")]),
+  ])));
+  let options = WatcherOptions { initial_backoff: Duration::from_millis(1), ..options() };
+  let (mut watcher, queries) = query_watcher(MemorySinceIdStore::new(), options, responses);
+
+  assert_eq!(ids(&watcher.poll().await), vec!["100", "102"]);
+  // retweet of an old tweet is new, as the retweet is
+  assert_eq!(ids(&watcher.poll().await), vec!["105", "50"]);
+  // the mark is only saved once the tweets have been handled
+  assert_eq!(watcher.store().get("query:from:a").unwrap(), Some(TweetId(102)));
+  watcher.commit().unwrap();
+  assert_eq!(watcher.store().get("query:from:a").unwrap(), Some(TweetId(110)));
+
+  // errors back off exponentially
+  for expected in [1, 2] {
+    match &watcher.poll().await[..] {
+      [WatchEvent::Error { retry_in, error, .. }] => {
+        assert_eq!(*retry_in, Duration::from_millis(expected));
+        assert_eq!(error.to_string(), "rate limited");
+      },
+      events => panic!("expected an error, got {events:?}"),
+    }
+  }
+  assert!(watcher.poll().await.is_empty());
+  assert_eq!(*queries.borrow(), vec![
+    "from:a",
+    "from:a since_id:102",
+    "from:a since_id:110",
+    "from:a since_id:110",
+    "from:a since_id:110",
+  ]);
+}
+
+/// a watcher of one query with the mark at 100, whose fetches return the
+/// page at the cursor (the tweets and the cursor of the next page). the
+/// cursors it fetches are recorded in the returned vec
+fn paged_watcher(options: WatcherOptions, pages: HashMap<&'static str, (Vec<&'static str>, Option<&'static str>)>
+) -> (Watcher<MemorySinceIdStore>, Rc<RefCell<Vec<String>>>) {
+  let cursors = Rc::new(RefCell::new(Vec::new()));
+  let cursors_clone = cursors.clone();
+  let mut store = MemorySinceIdStore::new();
+  store.set("query:from:a", TweetId(100)).unwrap();
+  let watcher = Watcher::with_fetch(vec![WatchSource::Query("from:a".to_string())], store, options,
+    move |_, cursor| {
+      let (ids, next_cursor) = pages[cursor.as_str()].clone();
+      cursors_clone.borrow_mut().push(cursor);
+      let page = (ids.into_iter().map(tweet).collect(), next_cursor.map(|cursor| cursor.to_string()));
+      async move { Ok(page) }
+    });
+  (watcher, cursors)
+}
+
+#[tokio::test]
+async fn watch_test_pages() {
+  println!("Watcher::poll()  //  follows the pages until one reaches the high-water mark");
+  let pages = HashMap::from([
+    ("", (vec!["110", "109"], Some("b1"))),
+    ("b1", (vec!["108", "107"], Some("b2"))),
+    ("b2", (vec!["106", "100"], Some("b3"))),
+  ]);
+  let (mut watcher, cursors) = paged_watcher(options(), pages.clone());
+  let events = watcher.poll().await;
+  assert_eq!(ids(&events), vec!["106", "107", "108", "109", "110"]);
+  assert!(!events.iter().any(|event| matches!(event, WatchEvent::Gap { .. })));
+  assert_eq!(*cursors.borrow(), vec!["", "b1", "b2"]);
+
+  // past the cap, tweets between 100 and the oldest fetched may be missing
+  let (mut watcher, cursors) = paged_watcher(WatcherOptions { max_pages: 2, ..options() }, pages);
+  let events = watcher.poll().await;
+  match &events[0] {
+    WatchEvent::Gap { since_id, until_id, .. } => assert_eq!((*since_id, *until_id), (TweetId(100), TweetId(107))),
+    event => panic!("expected a gap, got {event:?}"),
+  }
+  assert_eq!(ids(&events), vec!["107", "108", "109", "110"]);
+  assert_eq!(*cursors.borrow(), vec!["", "b1"]);
+}
+
+#[tokio::test]
+async fn watch_test_restart() {
+  println!("Watcher  //  high-water marks persist in a json file across restarts");
+  let dir = temp_dir("watch");
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("since_ids.json");
+
+  // the first run only sets the high-water mark
+  let store = JsonFileSinceIdStore::open(&path).unwrap();
+  let responses: Responses = Rc::new(RefCell::new(VecDeque::from([Ok(vec![tweet("7"), tweet("9")])])));
+  let options = WatcherOptions { emit_existing: false, ..options() };
+  let (mut watcher, _) = query_watcher(store, options.clone(), responses);
+  assert!(watcher.poll().await.is_empty());
+  drop(watcher);
+
+  let store = JsonFileSinceIdStore::open(&path).unwrap();
+  assert_eq!(store.get("query:from:a").unwrap(), Some(TweetId(9)));
+  let responses: Responses = Rc::new(RefCell::new(VecDeque::from([
+    Ok(vec![tweet("12"), tweet("9")]),
+    Ok(vec![tweet("13")]),
+  ])));
+  let (watcher, queries) = query_watcher(store, options.clone(), responses);
+  let events: Vec<WatchEvent> = watcher.into_stream().take(2).collect().await;
+  assert_eq!(ids(&events), vec!["12", "13"]);
+  assert_eq!(queries.borrow()[0], "from:a since_id:9");
+
+  // 12 was handled before 13 was requested, but 13 wasn't, so it's emitted
+  // again after a restart
+  let store = JsonFileSinceIdStore::open(&path).unwrap();
+  assert_eq!(store.get("query:from:a").unwrap(), Some(TweetId(12)));
+  let responses: Responses = Rc::new(RefCell::new(VecDeque::from([Ok(vec![tweet("13")])])));
+  let (mut watcher, queries) = query_watcher(store, options, responses);
+  assert_eq!(ids(&watcher.poll().await), vec!["13"]);
+  assert_eq!(queries.borrow()[0], "from:a since_id:12");
+  std::fs::remove_dir_all(&dir).ok();
+}